@@ -1,53 +1,78 @@
-use grammers_client::Update;
-use std::any::type_name;
-use telegram::{DefaultParser, TelegramAccount, TelegramConfig};
+use futures::future::join_all;
+use std::env;
+use std::sync::Arc;
+use telegram::{
+    list_dialogs_command, load_telegram_configs, Backfill, BackfillConfig, CallPipeline,
+    DefaultParser, ListenerConfig, PipelineConfig, TelegramAccount, TelegramListener,
+};
 
 mod telegram;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("lol");
+    let command = env::args().nth(1).unwrap_or_else(|| String::from("listen"));
 
-    let telegram_config = TelegramConfig::new("KEKI");
-    let telegram_account = TelegramAccount::new(telegram_config).await;
-    let telegram_parser = DefaultParser::new(telegram_account.clone());
+    // Login only produces the .session files and exits
+    if command == "login" {
+        for telegram_config in load_telegram_configs() {
+            TelegramAccount::login(&telegram_config).await?;
+        }
+        return Ok(());
+    }
 
-    // let chat =
-    // let me = telegram_account.client.iter_messages().await?;
-    // println!("{:?}", me);
-    // println!("{:?}", type_of(&me));
+    // Discovery of chat ids to track, `--json` for machine readable output
+    if command == "list-dialogs" {
+        let as_json = env::args().any(|arg| arg == "--json");
+        for telegram_config in load_telegram_configs() {
+            list_dialogs_command(&telegram_config, as_json).await?;
+        }
+        return Ok(());
+    }
 
-    // while let Some(update) = telegram_account.client.next_update().await? {
-    //     match update {
-    //         Update::NewMessage(message) if !message.outgoing() => {
-    //             if let Some(parse_result) = telegram_parser.parse(message).await? {
-    //                 println!("parse_result: {:?}", parse_result);
-    //             } else {
-    //                 continue;
-    //             }
-    //         }
-    //         _ => {}
-    //     }
-    // }
+    // 1. Log in every account listed in PREFIXES
+    let mut telegram_accounts: Vec<TelegramAccount> = Vec::new();
+    for telegram_config in load_telegram_configs() {
+        telegram_accounts.push(TelegramAccount::new(telegram_config).await);
+    }
 
-    /*
-    let solana_rpc_url = env::var("SOLANA_RPC_URL").unwrap();
-    let client = RpcClient::new(solana_rpc_url);
+    // 2. All accounts feed the same pipeline
+    let telegram_parser = DefaultParser::new(&telegram_accounts);
+    let pipeline = Arc::new(CallPipeline::new(telegram_parser, PipelineConfig::new())?);
 
-    let account_pubkey = Pubkey::from_str("9VffBiow5r5YQzgK56rirEWpu45gZGrDWzm9JUt6zL9G").unwrap();
-    let acc = client.get_account(&account_pubkey).unwrap();
+    match command.as_str() {
+        "listen" => {
+            let listeners: Vec<TelegramListener> = telegram_accounts
+                .into_iter()
+                .map(|account| {
+                    TelegramListener::new(account, pipeline.clone(), ListenerConfig::new())
+                })
+                .collect();
 
-    print!("acc: {:?}\n\n", acc);
+            for result in join_all(listeners.iter().map(|listener| listener.run())).await {
+                result?;
+            }
+        }
+        "backfill" => {
+            let backfills: Vec<Backfill> = telegram_accounts
+                .into_iter()
+                .map(|account| {
+                    let config = BackfillConfig::new(&account.prefix);
+                    Backfill::new(account, pipeline.clone(), config)
+                })
+                .collect();
 
-    // &account_pubkey, &acc.owner, &acc.data, None
-
-    let decoded_data = parse_token(&acc.data, None).unwrap();
-    print!("decoded_data: {:?}", decoded_data);
-    */
+            for result in join_all(backfills.iter().map(|backfill| backfill.run())).await {
+                result?;
+            }
+        }
+        _ => {
+            return Err(format!(
+                "Unknown command: {} (expected listen, backfill, login or list-dialogs)",
+                command
+            )
+            .into())
+        }
+    }
 
     Ok(())
 }
-
-fn type_of<T>(_: &T) -> &'static str {
-    type_name::<T>()
-}
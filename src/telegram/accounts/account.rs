@@ -1,14 +1,12 @@
-use grammers_client::{
-    types::{Chat, Dialog},
-    Client, Config,
-};
-use grammers_session::Session;
-use std::{
-    collections::HashMap,
-    io::{stdin, stdout, Write},
-};
+use grammers_client::{types::Chat, Client, Config, InitParams};
+use grammers_mtsender::FixedReconnect;
+use grammers_session::{PackedChat, PackedType, Session};
+use std::time::Duration;
 
-use super::TelegramConfig;
+use super::{
+    authorize, resolve_tracked_chats, TelegramConfig, TopicFilter, TopicRules, TrackedGroups,
+};
+use std::collections::HashMap;
 
 /// Refers to Telegram Chats/Channels/Groups
 #[derive(Clone)]
@@ -16,27 +14,122 @@ pub struct TelegramGroup {
     pub telegram_id: i64, // gramme.rs has telegram group ids as i64
     pub name: String,
     pub access_hash: Option<i64>,
+    pub chat_type: PackedType,
+    /// Forum topics to take calls from, allows everything for non-forum chats
+    pub topics: TopicFilter,
 }
 
+impl TelegramGroup {
+    pub fn from_chat(chat: &Chat) -> Self {
+        let packed_chat = chat.pack();
+
+        Self {
+            telegram_id: chat.id(),
+            name: chat.name().to_string(),
+            access_hash: packed_chat.access_hash,
+            chat_type: packed_chat.ty,
+            topics: TopicFilter::default(),
+        }
+    }
+
+    /// Rebuilds the packed form the client needs for requests like `iter_messages`
+    pub fn pack(&self) -> PackedChat {
+        PackedChat {
+            ty: self.chat_type,
+            id: self.telegram_id,
+            access_hash: self.access_hash,
+        }
+    }
+}
+
+/// Lets the underlying sender re-establish the connection after a network drop
+/// instead of failing every pending request.
+static RECONNECTION_POLICY: FixedReconnect = FixedReconnect {
+    attempts: 5,
+    delay: Duration::from_secs(5),
+};
+
 #[derive(Clone)]
 pub struct TelegramAccount {
+    pub prefix: String,
     pub client: Client,
-    pub tracked_groups: HashMap<i64, TelegramGroup>,
+    pub session_name: String,
+    pub tracked_groups: TrackedGroups,
 }
 
 impl TelegramAccount {
     pub async fn new(config: TelegramConfig) -> Self {
         let client = Self::init_client(&config).await.unwrap();
-        let tracked_groups = Self::init_tracked_groups(&config.tracked_chat_ids, &client)
-            .await
-            .unwrap();
+        let mut tracked_groups =
+            resolve_tracked_chats(&client, &config.prefix, &config.tracked_chats)
+                .await
+                .unwrap_or_else(|err| panic!("{}", err));
+        Self::apply_topic_rules(&config.prefix, &mut tracked_groups, &config.topic_rules);
 
         Self {
+            prefix: config.prefix,
             client,
-            tracked_groups,
+            session_name: config.session_name,
+            tracked_groups: TrackedGroups::new(tracked_groups),
         }
     }
 
+    /// Re-reads the tracked chats from the config and swaps in the newly resolved set.
+    /// On any error the current set is kept untouched. Returns the number of tracked groups.
+    pub async fn reload_tracked_groups(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let tracked_chats = TelegramConfig::reload_tracked_chats(&self.prefix)?;
+        let topic_rules = TelegramConfig::reload_topic_rules(&self.prefix)?;
+        let mut tracked_groups =
+            resolve_tracked_chats(&self.client, &self.prefix, &tracked_chats).await?;
+        Self::apply_topic_rules(&self.prefix, &mut tracked_groups, &topic_rules);
+
+        let count = tracked_groups.len();
+        self.tracked_groups.replace(tracked_groups);
+
+        Ok(count)
+    }
+
+    fn apply_topic_rules(
+        prefix: &str,
+        tracked_groups: &mut HashMap<i64, TelegramGroup>,
+        topic_rules: &TopicRules,
+    ) {
+        for chat_id in topic_rules.chat_ids() {
+            if !tracked_groups.contains_key(chat_id) {
+                eprintln!(
+                    "[{}] Topic rules set for chat {} which is not tracked, ignoring them",
+                    prefix, chat_id
+                );
+            }
+        }
+
+        for group in tracked_groups.values_mut() {
+            group.topics = topic_rules.filter_for(group.telegram_id);
+        }
+    }
+
+    /// Flushes the latest update state into the session and persists it to disk,
+    /// so the next start can catch up from where this one stopped.
+    pub fn save_session(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.client.sync_update_state();
+        self.client.session().save_to_file(&self.session_name)?;
+
+        Ok(())
+    }
+
+    /// Connects and signs the account in without tracking anything, for the `login` command
+    pub async fn login(config: &TelegramConfig) -> Result<(), Box<dyn std::error::Error>> {
+        Self::connect(config).await?;
+
+        Ok(())
+    }
+
+    /// Returns a signed in client without resolving the tracked chats,
+    /// for commands that only need to talk to Telegram
+    pub async fn connect(config: &TelegramConfig) -> Result<Client, Box<dyn std::error::Error>> {
+        Self::init_client(config).await
+    }
+
     async fn init_client(config: &TelegramConfig) -> Result<Client, Box<dyn std::error::Error>> {
         let TelegramConfig {
             api_id,
@@ -50,59 +143,15 @@ impl TelegramAccount {
             session,
             api_id: *api_id,
             api_hash: api_hash.clone(),
-            params: Default::default(),
+            params: InitParams {
+                reconnection_policy: &RECONNECTION_POLICY,
+                ..Default::default()
+            },
         })
         .await?;
 
-        if !client.is_authorized().await? {
-            let phone_number = Self::prompt("Enter your phone number: ");
-            let request_result = client.request_login_code(&phone_number).await?;
-            let code = Self::prompt("Enter the code you received: ");
-
-            client.sign_in(&request_result, &code).await?;
-            client.session().save_to_file(session_name)?;
-        }
+        authorize(&client, config).await?;
 
         Ok(client)
     }
-
-    fn prompt(message: &str) -> String {
-        let mut input = String::new();
-        print!("{}", message);
-        stdout().flush().expect("Failed to flush stdout");
-        stdin().read_line(&mut input).expect("Failed to read line");
-        input.trim().to_string()
-    }
-
-    pub async fn init_tracked_groups(
-        chat_ids: &[i64],
-        client: &Client,
-    ) -> Result<HashMap<i64, TelegramGroup>, Box<dyn std::error::Error>> {
-        let mut dialogs_iterator = client.iter_dialogs();
-        let mut dialogs_to_track: Vec<Dialog> = Vec::new();
-
-        while let Some(dialog) = dialogs_iterator.next().await? {
-            let chat = dialog.chat();
-            if chat_ids.contains(&chat.id()) {
-                dialogs_to_track.push(dialog);
-            }
-        }
-
-        // 2. Transform the tracked dialogs into `TelegramGroup` instances
-        let tracked_groups: HashMap<i64, TelegramGroup> = dialogs_to_track
-            .into_iter()
-            .map(|dialog| {
-                let chat = dialog.chat();
-                let chat_id = chat.id();
-                let telegram_group = TelegramGroup {
-                    telegram_id: chat_id,
-                    name: chat.name().to_string(),
-                    access_hash: chat.pack().access_hash,
-                };
-                (chat_id, telegram_group)
-            })
-            .collect();
-
-        Ok(tracked_groups)
-    }
 }
@@ -1,12 +1,153 @@
 use dotenv::dotenv;
-use std::env;
+use std::path::{Path, PathBuf};
+use std::{collections::HashMap, env, fmt, fs, time::SystemTime};
+
+/// Messages of a forum's General topic carry no topic id
+pub const GENERAL_TOPIC_ID: i32 = 1;
+
+/// One entry of the tracked chats list, as written in the config
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatReference {
+    /// Numeric id as used by gramme.rs, only resolvable for chats in the dialog list
+    Id(i64),
+    /// `@channel`, `channel` or `https://t.me/channel`
+    Username(String),
+    /// `https://t.me/+hash` or `https://t.me/joinchat/hash`
+    InviteHash(String),
+}
+
+impl ChatReference {
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let entry = entry.trim();
+
+        if let Ok(id) = entry.parse::<i64>() {
+            return Ok(Self::Id(id));
+        }
+
+        if let Some(username) = entry.strip_prefix('@') {
+            return Self::parse_username(username, entry);
+        }
+
+        // Links: strip scheme and domain, what's left is the path
+        let link = entry
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.");
+        let path = ["t.me/", "telegram.me/", "telegram.dog/"]
+            .iter()
+            .find_map(|domain| link.strip_prefix(domain));
+
+        let path = match path {
+            Some(path) => path.trim_end_matches('/'),
+            None => return Self::parse_username(entry, entry),
+        };
+
+        if let Some(hash) = path.strip_prefix('+') {
+            return Ok(Self::InviteHash(hash.to_string()));
+        }
+        if let Some(hash) = path.strip_prefix("joinchat/") {
+            return Ok(Self::InviteHash(hash.to_string()));
+        }
+
+        // t.me/s/channel is the web preview, t.me/channel/123 links a single message
+        let path = path.strip_prefix("s/").unwrap_or(path);
+        let username = path.split(['/', '?']).next().unwrap_or_default();
+        Self::parse_username(username, entry)
+    }
+
+    fn parse_username(username: &str, entry: &str) -> Result<Self, String> {
+        let is_valid = username.len() >= 4
+            && username.starts_with(|c: char| c.is_ascii_alphabetic())
+            && username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if is_valid {
+            Ok(Self::Username(username.to_string()))
+        } else {
+            Err(format!("Not a chat id, @username or t.me link: {}", entry))
+        }
+    }
+}
+
+impl fmt::Display for ChatReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Username(username) => write!(f, "@{}", username),
+            Self::InviteHash(hash) => write!(f, "https://t.me/+{}", hash),
+        }
+    }
+}
+
+/// Which forum topics of a tracked group produce calls.
+/// An empty include list means every topic that isn't excluded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicFilter {
+    pub included: Vec<i32>,
+    pub excluded: Vec<i32>,
+}
+
+impl TopicFilter {
+    /// `topic_id` is None outside of forums and for the General topic
+    pub fn allows(&self, topic_id: Option<i32>) -> bool {
+        let topic_id = topic_id.unwrap_or(GENERAL_TOPIC_ID);
+
+        !self.excluded.contains(&topic_id)
+            && (self.included.is_empty() || self.included.contains(&topic_id))
+    }
+}
+
+/// Per chat topic filters, from `{PREFIX}_INCLUDED_TOPICS` and `{PREFIX}_EXCLUDED_TOPICS`.
+/// Both are comma separated `chat_id:topic_id` entries, e.g. `1234567890:5,1234567890:12`
+#[derive(Debug, Clone, Default)]
+pub struct TopicRules(HashMap<i64, TopicFilter>);
+
+impl TopicRules {
+    pub fn parse(included: &str, excluded: &str) -> Result<Self, String> {
+        let mut rules: HashMap<i64, TopicFilter> = HashMap::new();
+
+        for (chat_id, topic_id) in Self::parse_entries(included)? {
+            rules.entry(chat_id).or_default().included.push(topic_id);
+        }
+        for (chat_id, topic_id) in Self::parse_entries(excluded)? {
+            rules.entry(chat_id).or_default().excluded.push(topic_id);
+        }
+
+        Ok(Self(rules))
+    }
+
+    fn parse_entries(comma_separated_entries: &str) -> Result<Vec<(i64, i32)>, String> {
+        comma_separated_entries
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let parsed = entry.split_once(':').and_then(|(chat_id, topic_id)| {
+                    Some((chat_id.trim().parse().ok()?, topic_id.trim().parse().ok()?))
+                });
+                parsed.ok_or_else(|| format!("Not a chat_id:topic_id entry: {}", entry))
+            })
+            .collect()
+    }
+
+    pub fn filter_for(&self, chat_id: i64) -> TopicFilter {
+        self.0.get(&chat_id).cloned().unwrap_or_default()
+    }
+
+    pub fn chat_ids(&self) -> impl Iterator<Item = &i64> {
+        self.0.keys()
+    }
+}
 
 #[derive(Debug)]
 pub struct TelegramConfig {
+    pub prefix: String,
     pub api_id: i32,
     pub api_hash: String,
     pub session_name: String,
-    pub tracked_chat_ids: Vec<i64>,
+    pub tracked_chats: Vec<ChatReference>,
+    pub topic_rules: TopicRules,
 }
 
 impl TelegramConfig {
@@ -14,54 +155,224 @@ impl TelegramConfig {
         dotenv().ok();
 
         Self {
+            prefix: prefix.to_string(),
             api_id: env::var(format!("{}_API_ID", prefix))
                 .unwrap()
                 .parse()
                 .unwrap(),
             api_hash: env::var(format!("{}_API_HASH", prefix)).unwrap(),
             session_name: format!("{}.{}", prefix.to_lowercase(), "session"),
-            tracked_chat_ids: Self::load_tracked_chat_ids(&format!("{}_TRACKED_CHAT_IDS", prefix)),
+            tracked_chats: Self::load_tracked_chats(&format!("{}_TRACKED_CHAT_IDS", prefix)),
+            topic_rules: Self::load_topic_rules(prefix),
+        }
+    }
+
+    fn load_topic_rules(prefix: &str) -> TopicRules {
+        let included = env::var(format!("{}_INCLUDED_TOPICS", prefix)).unwrap_or_default();
+        let excluded = env::var(format!("{}_EXCLUDED_TOPICS", prefix)).unwrap_or_default();
+
+        match TopicRules::parse(&included, &excluded) {
+            Ok(topic_rules) => topic_rules,
+            Err(err) => panic!("Could not parse topic rules for {}: {}", prefix, err),
         }
     }
 
-    fn load_tracked_chat_ids(env_var: &str) -> Vec<i64> {
-        let comma_separated_ids = match env::var(env_var) {
-            Ok(ids) => ids,
+    fn load_tracked_chats(env_var: &str) -> Vec<ChatReference> {
+        // Missing is allowed so `list-dialogs` can be used to discover the ids in the first place
+        let comma_separated_chats = match env::var(env_var) {
+            Ok(chats) => chats,
             Err(err) => {
-                eprintln!("Could not load {} var: {}", env_var, err);
-                panic!("Panic due to env var error");
+                eprintln!(
+                    "Could not load {} var: {}, no chats will be tracked",
+                    env_var, err
+                );
+                return Vec::new();
             }
         };
 
-        let mut tracked_chat_ids: Vec<i64> = Vec::new();
-        for id in comma_separated_ids.split(",") {
-            let trimmed_id = id.trim();
-            let parsed_id = match trimmed_id.parse::<i64>() {
-                Ok(id) => id,
-                Err(_) => {
-                    panic!("Could not convert string to i64 for chat id: {}", id.trim());
+        match Self::parse_tracked_chats(&comma_separated_chats) {
+            Ok(tracked_chats) => tracked_chats,
+            Err(err) => panic!("Could not parse tracked chat in {}: {}", env_var, err),
+        }
+    }
+
+    fn parse_tracked_chats(comma_separated_chats: &str) -> Result<Vec<ChatReference>, String> {
+        comma_separated_chats
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(ChatReference::parse)
+            .collect()
+    }
+
+    /// Re-reads `{PREFIX}_TRACKED_CHAT_IDS`, preferring the current contents of the .env file
+    /// over the process environment (dotenv never overrides variables that are already set)
+    pub fn reload_tracked_chats(
+        prefix: &str,
+    ) -> Result<Vec<ChatReference>, Box<dyn std::error::Error>> {
+        let comma_separated_chats = Self::reload_var(&format!("{}_TRACKED_CHAT_IDS", prefix));
+
+        Ok(Self::parse_tracked_chats(&comma_separated_chats)?)
+    }
+
+    /// Re-reads the topic rules the same way as `reload_tracked_chats`
+    pub fn reload_topic_rules(prefix: &str) -> Result<TopicRules, Box<dyn std::error::Error>> {
+        let included = Self::reload_var(&format!("{}_INCLUDED_TOPICS", prefix));
+        let excluded = Self::reload_var(&format!("{}_EXCLUDED_TOPICS", prefix));
+
+        Ok(TopicRules::parse(&included, &excluded)?)
+    }
+
+    fn reload_var(key: &str) -> String {
+        let value = dotenv()
+            .ok()
+            .and_then(|path| Self::read_dotenv_var(&path, key));
+
+        match value {
+            Some(value) => value,
+            None => env::var(key).unwrap_or_default(),
+        }
+    }
+
+    /// Value of `key` in a .env file, parsed by dotenv itself so `export`, quotes
+    /// and comments are read the same way as at startup
+    /// `from_path` can't be used instead, as it never overrides variables already set.
+    #[allow(deprecated)]
+    fn read_dotenv_var(path: &Path, key: &str) -> Option<String> {
+        dotenv::from_path_iter(path)
+            .ok()?
+            .filter_map(Result::ok)
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// True for the line assigning `key`, e.g. `KEY=...`, `KEY = ...` or `export KEY=...`
+    fn assigns(line: &str, key: &str) -> bool {
+        let line = line.trim_start();
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        line.strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    }
+
+    /// Appends chat entries to `{PREFIX}_TRACKED_CHAT_IDS` in the loaded .env file,
+    /// skipping entries already tracked. Returns the path of the updated file.
+    pub fn append_tracked_chats(
+        prefix: &str,
+        entries: &[String],
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = dotenv().unwrap_or_else(|_| PathBuf::from(".env"));
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let key = format!("{}_TRACKED_CHAT_IDS", prefix);
+
+        // 1. Merge the new entries into the current value, as dotenv parses it
+        let mut chats: Vec<String> = Self::read_dotenv_var(&path, &key)
+            .unwrap_or_default()
+            .split(',')
+            .map(|chat| chat.trim().to_string())
+            .filter(|chat| !chat.is_empty())
+            .collect();
+        for entry in entries {
+            if !chats.contains(entry) {
+                chats.push(entry.clone());
+            }
+        }
+
+        // 2. Replace the line assigning it, quoted so any entry survives the next parse
+        let mut found = false;
+        let mut lines: Vec<String> = contents
+            .lines()
+            .map(|line| {
+                if !found && Self::assigns(line, &key) {
+                    found = true;
+                    let export = if line.trim_start().starts_with("export ") {
+                        "export "
+                    } else {
+                        ""
+                    };
+                    format!("{}{}=\"{}\"", export, key, chats.join(","))
+                } else {
+                    line.to_string()
                 }
-            };
-            tracked_chat_ids.push(parsed_id);
+            })
+            .collect();
+
+        if !found {
+            lines.push(format!("{}=\"{}\"", key, chats.join(",")));
         }
 
-        tracked_chat_ids
+        fs::write(&path, lines.join("\n") + "\n")?;
+
+        Ok(path)
     }
 }
 
-/*
-pub fn load_telegram_configs() -> Result<Vec<TelegramConfig>, Error> {
-    let prefixes: Vec<TelegramConfig> = env::var("PREFIXES")
-        .unwrap()
+/// Loads one `TelegramConfig` per account listed in the comma separated `PREFIXES` env var
+pub fn load_telegram_configs() -> Vec<TelegramConfig> {
+    dotenv().ok();
+
+    let prefixes = match env::var("PREFIXES") {
+        Ok(prefixes) => prefixes,
+        Err(err) => {
+            eprintln!("Could not load PREFIXES var: {}", err);
+            panic!("Panic due to env var error");
+        }
+    };
+
+    let configs: Vec<TelegramConfig> = prefixes
         .to_uppercase()
         .split(',')
-        .map(|s| TelegramConfig::new(s.trim()))
+        .map(|prefix| prefix.trim())
+        .filter(|prefix| !prefix.is_empty())
+        .map(TelegramConfig::new)
         .collect();
 
-    if prefixes.is_empty() {
+    if configs.is_empty() {
         panic!("PREFIXES env var not set properly!");
     }
 
-    Ok(prefixes)
+    configs
+}
+
+/// Polls the modification time of the .env file so config edits can be picked up live
+pub struct ConfigWatcher {
+    path: Option<PathBuf>,
+    last_modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        let path = dotenv().ok();
+        let last_modified = path.as_ref().and_then(Self::modified);
+
+        Self {
+            path,
+            last_modified,
+        }
+    }
+
+    /// True once per modification of the watched file
+    pub fn changed(&mut self) -> bool {
+        let modified = match self.path.as_ref().and_then(Self::modified) {
+            Some(modified) => modified,
+            None => return false,
+        };
+
+        if self.last_modified == Some(modified) {
+            return false;
+        }
+
+        self.last_modified = Some(modified);
+        true
+    }
+
+    fn modified(path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
-*/
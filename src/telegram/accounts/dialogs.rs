@@ -0,0 +1,130 @@
+use super::{prompt, TelegramAccount, TelegramConfig};
+use grammers_client::{types::Chat, Client};
+use grammers_session::PackedType;
+use serde::Serialize;
+use std::io::{stdin, IsTerminal};
+
+/// One row of the `list-dialogs` output
+#[derive(Debug, Serialize)]
+pub struct DialogInfo {
+    pub telegram_id: i64,
+    pub chat_type: &'static str,
+    pub name: String,
+    pub username: Option<String>,
+    pub access_hash: Option<i64>,
+}
+
+impl DialogInfo {
+    fn from_chat(chat: &Chat) -> Self {
+        let packed_chat = chat.pack();
+        let chat_type = match packed_chat.ty {
+            PackedType::User => "user",
+            PackedType::Bot => "bot",
+            PackedType::Chat => "group",
+            PackedType::Megagroup => "megagroup",
+            PackedType::Gigagroup => "gigagroup",
+            PackedType::Broadcast => "channel",
+        };
+
+        Self {
+            telegram_id: chat.id(),
+            chat_type,
+            name: chat.name().to_string(),
+            username: chat.username().map(str::to_string),
+            access_hash: packed_chat.access_hash,
+        }
+    }
+}
+
+/// Every dialog of the account, in the order Telegram returns them (most recent first)
+pub async fn list_dialogs(client: &Client) -> Result<Vec<DialogInfo>, Box<dyn std::error::Error>> {
+    let mut dialogs: Vec<DialogInfo> = Vec::new();
+    let mut dialogs_iterator = client.iter_dialogs();
+
+    while let Some(dialog) = dialogs_iterator.next().await? {
+        dialogs.push(DialogInfo::from_chat(dialog.chat()));
+    }
+
+    Ok(dialogs)
+}
+
+pub fn print_dialogs_table(dialogs: &[DialogInfo]) {
+    let name_width = dialogs
+        .iter()
+        .map(|dialog| dialog.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(4, 40);
+
+    println!(
+        "{:<20} {:<10} {:<name_width$} {:<33} ACCESS HASH",
+        "ID", "TYPE", "NAME", "USERNAME"
+    );
+    for dialog in dialogs {
+        let name: String = dialog.name.chars().take(name_width).collect();
+        println!(
+            "{:<20} {:<10} {:<name_width$} {:<33} {}",
+            dialog.telegram_id,
+            dialog.chat_type,
+            name,
+            dialog
+                .username
+                .as_deref()
+                .map(|username| format!("@{}", username))
+                .unwrap_or_default(),
+            dialog
+                .access_hash
+                .map(|hash| hash.to_string())
+                .unwrap_or_default(),
+        );
+    }
+}
+
+/// `list-dialogs` command: prints the account's dialogs as a table or JSON and,
+/// when run from a terminal, offers to add some of them to the tracked chats
+pub async fn list_dialogs_command(
+    config: &TelegramConfig,
+    as_json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = TelegramAccount::connect(config).await?;
+    let dialogs = list_dialogs(&client).await?;
+
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&dialogs)?);
+        return Ok(());
+    }
+
+    println!("[{}] {} dialogs", config.prefix, dialogs.len());
+    print_dialogs_table(&dialogs);
+
+    if !stdin().is_terminal() {
+        return Ok(());
+    }
+
+    let selection = prompt("Enter ids to track (comma separated, empty to skip): ");
+    let mut selected_ids: Vec<String> = Vec::new();
+    for id in selection
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+    {
+        match id.parse::<i64>() {
+            Ok(id) if dialogs.iter().any(|dialog| dialog.telegram_id == id) => {
+                selected_ids.push(id.to_string())
+            }
+            _ => eprintln!("Skipping {}: not one of the listed dialog ids", id),
+        }
+    }
+
+    if !selected_ids.is_empty() {
+        let path = TelegramConfig::append_tracked_chats(&config.prefix, &selected_ids)?;
+        println!(
+            "Added {} to {}_TRACKED_CHAT_IDS in {}",
+            selected_ids.join(","),
+            config.prefix,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
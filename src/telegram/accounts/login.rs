@@ -0,0 +1,149 @@
+use super::TelegramConfig;
+use dotenv::dotenv;
+use grammers_client::{Client, SignInError};
+use std::{
+    env, fs,
+    io::{stdin, stdout, IsTerminal, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+/// How often the login code file is checked while waiting for it to be written
+const CODE_FILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Source of one login credential, in the order they are tried:
+/// `{PREFIX}_{NAME}` env var, file at `{PREFIX}_{NAME}_FILE`, then an interactive prompt
+/// when stdin is a terminal.
+struct Credential {
+    env_var: String,
+    file_env_var: String,
+    prompt: &'static str,
+}
+
+impl Credential {
+    fn new(prefix: &str, name: &str, prompt: &'static str) -> Self {
+        Self {
+            env_var: format!("{}_{}", prefix, name),
+            file_env_var: format!("{}_{}_FILE", prefix, name),
+            prompt,
+        }
+    }
+
+    fn read_env_or_file(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if let Ok(value) = env::var(&self.env_var) {
+            return Ok(Some(value.trim().to_string()));
+        }
+
+        match env::var(&self.file_env_var) {
+            Ok(path) => Ok(Some(fs::read_to_string(path)?.trim().to_string())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn load(&self) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(value) = self.read_env_or_file()? {
+            return Ok(value);
+        }
+
+        if stdin().is_terminal() {
+            return Ok(prompt(self.prompt));
+        }
+
+        Err(format!(
+            "Missing login credential: set {} or {}, or run the login command from a terminal",
+            self.env_var, self.file_env_var
+        )
+        .into())
+    }
+
+    /// The login code only exists after it was requested, so a configured code file is
+    /// waited on until something writes the code into it
+    async fn wait_for_code(&self, timeout: Duration) -> Result<String, Box<dyn std::error::Error>> {
+        if let Ok(value) = env::var(&self.env_var) {
+            return Ok(value.trim().to_string());
+        }
+
+        let path = match env::var(&self.file_env_var) {
+            Ok(path) => path,
+            Err(_) => return self.load(),
+        };
+
+        println!("Waiting for the login code to be written to {}", path);
+        let started_at = Instant::now();
+        loop {
+            if Path::new(&path).exists() {
+                let code = fs::read_to_string(&path)?.trim().to_string();
+                if !code.is_empty() {
+                    // Consume the code so a stale one isn't reused on the next login
+                    fs::remove_file(&path)?;
+                    return Ok(code);
+                }
+            }
+
+            if started_at.elapsed() >= timeout {
+                return Err(format!("Timed out waiting for login code in {}", path).into());
+            }
+            sleep(CODE_FILE_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Signs the client in if its session isn't authorized yet, including the 2FA
+/// cloud password step, and saves the resulting session
+pub async fn authorize(
+    client: &Client,
+    config: &TelegramConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    if client.is_authorized().await? {
+        return Ok(());
+    }
+
+    let prefix = &config.prefix;
+    let phone = Credential::new(prefix, "PHONE", "Enter your phone number: ");
+    let code = Credential::new(prefix, "LOGIN_CODE", "Enter the code you received: ");
+    let password = Credential::new(prefix, "PASSWORD", "Enter your 2FA password: ");
+    let code_timeout = Duration::from_secs(
+        env::var("LOGIN_CODE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.trim().parse().ok())
+            .unwrap_or(300),
+    );
+
+    // 1. Request the login code for the account's phone number
+    let login_token = client.request_login_code(&phone.load()?).await?;
+    let code = code.wait_for_code(code_timeout).await?;
+
+    // 2. Sign in, falling back to the cloud password if the account has 2FA enabled
+    match client.sign_in(&login_token, &code).await {
+        Ok(_) => {}
+        Err(SignInError::PasswordRequired(password_token)) => {
+            if let Some(hint) = password_token.hint() {
+                println!("2FA password required (hint: {})", hint);
+            }
+            client
+                .check_password(password_token, password.load()?)
+                .await?;
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    // 3. Persist the authorized session so later runs skip the login
+    client.session().save_to_file(&config.session_name)?;
+    println!(
+        "[{}] Logged in, session saved to {}",
+        prefix, config.session_name
+    );
+
+    Ok(())
+}
+
+pub fn prompt(message: &str) -> String {
+    let mut input = String::new();
+    print!("{}", message);
+    stdout().flush().expect("Failed to flush stdout");
+    stdin().read_line(&mut input).expect("Failed to read line");
+    input.trim().to_string()
+}
@@ -1,5 +1,13 @@
 mod account;
 mod config;
+mod dialogs;
+mod login;
+mod resolver;
+mod tracked;
 
 pub use account::*;
 pub use config::*;
+pub use dialogs::*;
+pub use login::*;
+pub use resolver::*;
+pub use tracked::*;
@@ -0,0 +1,150 @@
+use super::{ChatReference, TelegramGroup, TopicFilter};
+use grammers_client::{types::Chat, Client};
+use grammers_session::PackedType;
+use grammers_tl_types as tl;
+use std::collections::HashMap;
+
+/// A tracked chat entry after resolution
+pub struct ResolvedChat {
+    pub group: TelegramGroup,
+    /// Only joined chats deliver live updates
+    pub joined: bool,
+}
+
+/// Resolves every configured chat reference into a `TelegramGroup`, reporting whether the
+/// account has joined it. Entries that can't be resolved are collected into one error.
+pub async fn resolve_tracked_chats(
+    client: &Client,
+    prefix: &str,
+    chats: &[ChatReference],
+) -> Result<HashMap<i64, TelegramGroup>, Box<dyn std::error::Error>> {
+    // 1. The dialog list tells us which chats this account has joined
+    let mut joined_chats: HashMap<i64, Chat> = HashMap::new();
+    let mut dialogs_iterator = client.iter_dialogs();
+    while let Some(dialog) = dialogs_iterator.next().await? {
+        let chat = dialog.chat();
+        joined_chats.insert(chat.id(), chat.clone());
+    }
+
+    // 2. Resolve each entry on its own so one bad entry doesn't hide the others
+    let mut tracked_groups: HashMap<i64, TelegramGroup> = HashMap::new();
+    let mut failures: Vec<String> = Vec::new();
+
+    for reference in chats {
+        match resolve_chat(client, &joined_chats, reference).await {
+            Ok(resolved) => {
+                let group = resolved.group;
+                if resolved.joined {
+                    println!(
+                        "[{}] Tracking {} ({}) from {}",
+                        prefix, group.name, group.telegram_id, reference
+                    );
+                } else {
+                    eprintln!(
+                        "[{}] Tracking {} ({}) from {}, but the account has not joined it: no live updates will arrive",
+                        prefix, group.name, group.telegram_id, reference
+                    );
+                }
+                tracked_groups.insert(group.telegram_id, group);
+            }
+            Err(err) => failures.push(format!("  {}: {}", reference, err)),
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(format!(
+            "[{}] Could not resolve tracked chats:\n{}",
+            prefix,
+            failures.join("\n")
+        )
+        .into());
+    }
+
+    Ok(tracked_groups)
+}
+
+async fn resolve_chat(
+    client: &Client,
+    joined_chats: &HashMap<i64, Chat>,
+    reference: &ChatReference,
+) -> Result<ResolvedChat, String> {
+    match reference {
+        ChatReference::Id(id) => match joined_chats.get(id) {
+            Some(chat) => Ok(ResolvedChat {
+                group: TelegramGroup::from_chat(chat),
+                joined: true,
+            }),
+            None => Err(String::from(
+                "not in the account's dialogs, join it or track it by @username or t.me link",
+            )),
+        },
+        ChatReference::Username(username) => match client.resolve_username(username).await {
+            Ok(Some(chat)) => Ok(ResolvedChat {
+                joined: joined_chats.contains_key(&chat.id()),
+                group: TelegramGroup::from_chat(&chat),
+            }),
+            Ok(None) => Err(String::from("username does not exist")),
+            Err(err) => Err(format!("username resolution failed: {}", err)),
+        },
+        ChatReference::InviteHash(hash) => {
+            let request = tl::functions::messages::CheckChatInvite { hash: hash.clone() };
+            match client.invoke(&request).await {
+                Ok(tl::enums::ChatInvite::Already(invite)) => Ok(ResolvedChat {
+                    group: group_from_raw(&invite.chat)?,
+                    joined: true,
+                }),
+                Ok(tl::enums::ChatInvite::Peek(invite)) => Ok(ResolvedChat {
+                    group: group_from_raw(&invite.chat)?,
+                    joined: false,
+                }),
+                // Telegram only reveals the chat id of a private invite after joining
+                Ok(tl::enums::ChatInvite::Invite(invite)) => Err(format!(
+                    "valid invite for \"{}\" but the account has not joined it yet",
+                    invite.title
+                )),
+                Err(err) if err.is("INVITE_HASH_EXPIRED") => {
+                    Err(String::from("invite link has expired"))
+                }
+                Err(err) if err.is("INVITE_HASH_INVALID") => {
+                    Err(String::from("invite link is invalid"))
+                }
+                Err(err) => Err(format!("invite check failed: {}", err)),
+            }
+        }
+    }
+}
+
+/// Builds a `TelegramGroup` from a raw chat returned by invite checks
+fn group_from_raw(chat: &tl::enums::Chat) -> Result<TelegramGroup, String> {
+    let (telegram_id, name, access_hash, chat_type) = match chat {
+        tl::enums::Chat::Chat(chat) => (chat.id, chat.title.clone(), None, PackedType::Chat),
+        tl::enums::Chat::Channel(channel) => {
+            let chat_type = if channel.gigagroup {
+                PackedType::Gigagroup
+            } else if channel.megagroup {
+                PackedType::Megagroup
+            } else {
+                PackedType::Broadcast
+            };
+            (
+                channel.id,
+                channel.title.clone(),
+                channel.access_hash,
+                chat_type,
+            )
+        }
+        tl::enums::Chat::Empty(_)
+        | tl::enums::Chat::Forbidden(_)
+        | tl::enums::Chat::ChannelForbidden(_) => {
+            return Err(String::from("chat is not accessible to this account"))
+        }
+    };
+
+    Ok(TelegramGroup {
+        telegram_id,
+        name,
+        access_hash,
+        chat_type,
+        topics: TopicFilter::default(),
+    })
+}
@@ -0,0 +1,31 @@
+use super::TelegramGroup;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// The set of groups an account tracks, shared between the account, the listener and the
+/// parser. Reloading swaps the whole map, so every holder sees the new membership at once.
+#[derive(Clone, Default)]
+pub struct TrackedGroups(Arc<RwLock<HashMap<i64, TelegramGroup>>>);
+
+impl TrackedGroups {
+    pub fn new(groups: HashMap<i64, TelegramGroup>) -> Self {
+        Self(Arc::new(RwLock::new(groups)))
+    }
+
+    pub fn contains(&self, chat_id: i64) -> bool {
+        self.0.read().unwrap().contains_key(&chat_id)
+    }
+
+    pub fn get(&self, chat_id: i64) -> Option<TelegramGroup> {
+        self.0.read().unwrap().get(&chat_id).cloned()
+    }
+
+    /// Copy of the current groups, safe to hold across awaits
+    pub fn snapshot(&self) -> Vec<TelegramGroup> {
+        self.0.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn replace(&self, groups: HashMap<i64, TelegramGroup>) {
+        *self.0.write().unwrap() = groups;
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Progress of the backfill for a single chat
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChatCheckpoint {
+    /// Oldest message id processed so far, iteration resumes below it
+    pub offset_id: Option<i32>,
+    pub completed: bool,
+}
+
+/// Backfill progress for every tracked chat, persisted as JSON keyed by chat id
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BackfillCheckpoints {
+    pub chats: HashMap<i64, ChatCheckpoint>,
+}
+
+impl BackfillCheckpoints {
+    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // Write to a temp file first so a crash mid-write never corrupts the checkpoint
+        let tmp_path = format!("{}.tmp", path);
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, chat_id: i64) -> Option<&ChatCheckpoint> {
+        self.chats.get(&chat_id)
+    }
+
+    pub fn record(&mut self, chat_id: i64, message_id: i32) {
+        self.chats.entry(chat_id).or_default().offset_id = Some(message_id);
+    }
+
+    pub fn complete(&mut self, chat_id: i64) {
+        self.chats.entry(chat_id).or_default().completed = true;
+    }
+}
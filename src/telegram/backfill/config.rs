@@ -0,0 +1,62 @@
+use crate::telegram::positive_env_or;
+use chrono::{DateTime, Utc};
+use dotenv::dotenv;
+use std::env;
+
+#[derive(Debug)]
+pub struct BackfillConfig {
+    /// Stop walking back once messages are older than this date
+    pub until_date: Option<DateTime<Utc>>,
+    /// Stop walking back once this message id (inclusive) is reached
+    pub until_message_id: Option<i32>,
+    pub checkpoint_file: String,
+    /// How many processed messages between checkpoint writes
+    pub checkpoint_every: usize,
+    /// How many messages have their Solana accounts fetched together before being parsed
+    pub batch_size: usize,
+}
+
+impl BackfillConfig {
+    pub fn new(prefix: &str) -> Self {
+        dotenv().ok();
+
+        Self {
+            until_date: Self::load_until_date(&format!("{}_BACKFILL_UNTIL_DATE", prefix)),
+            until_message_id: env::var(format!("{}_BACKFILL_UNTIL_MESSAGE_ID", prefix))
+                .ok()
+                .map(|id| match id.trim().parse::<i32>() {
+                    Ok(id) => id,
+                    Err(_) => panic!("Could not convert string to i32 for message id: {}", id),
+                }),
+            checkpoint_file: format!("{}.{}", prefix.to_lowercase(), "backfill.json"),
+            checkpoint_every: positive_env_or("BACKFILL_CHECKPOINT_EVERY", 100),
+            batch_size: positive_env_or("BACKFILL_BATCH_SIZE", 100),
+        }
+    }
+
+    /// Accepts an RFC 3339 date like `2024-04-01T00:00:00Z`
+    fn load_until_date(env_var: &str) -> Option<DateTime<Utc>> {
+        let value = env::var(env_var).ok()?;
+        match DateTime::parse_from_rfc3339(value.trim()) {
+            Ok(date) => Some(date.with_timezone(&Utc)),
+            Err(err) => panic!("Could not parse {} as RFC 3339 date: {}", env_var, err),
+        }
+    }
+
+    /// True once the backfill has walked past the configured date or message id
+    pub fn is_past_limit(&self, message_id: i32, date: DateTime<Utc>) -> bool {
+        if let Some(until_message_id) = self.until_message_id {
+            if message_id <= until_message_id {
+                return true;
+            }
+        }
+
+        if let Some(until_date) = self.until_date {
+            if date < until_date {
+                return true;
+            }
+        }
+
+        false
+    }
+}
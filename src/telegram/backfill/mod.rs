@@ -0,0 +1,7 @@
+mod checkpoint;
+mod config;
+mod runner;
+
+pub use checkpoint::*;
+pub use config::*;
+pub use runner::*;
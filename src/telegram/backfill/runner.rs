@@ -0,0 +1,168 @@
+use super::{BackfillCheckpoints, BackfillConfig};
+use crate::telegram::{shutdown_signal, CallPipeline, TelegramAccount, TelegramGroup};
+use futures::{stream, StreamExt};
+use grammers_client::types::Message;
+use grammers_mtsender::InvocationError;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Used when Telegram reports a FLOOD_WAIT without telling us how long to wait
+const DEFAULT_FLOOD_WAIT_SECS: u32 = 60;
+
+/// Walks the history of every tracked group and runs it through the call pipeline
+pub struct Backfill {
+    telegram_account: TelegramAccount,
+    pipeline: Arc<CallPipeline>,
+    config: BackfillConfig,
+}
+
+impl Backfill {
+    pub fn new(
+        telegram_account: TelegramAccount,
+        pipeline: Arc<CallPipeline>,
+        config: BackfillConfig,
+    ) -> Self {
+        Self {
+            telegram_account,
+            pipeline,
+            config,
+        }
+    }
+
+    /// Backfills all tracked groups, resuming from the stored checkpoint.
+    /// Progress is saved on SIGINT/SIGTERM so the next run picks up where this one stopped.
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut checkpoints = BackfillCheckpoints::load(&self.config.checkpoint_file)?;
+
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+
+        for group in &self.telegram_account.tracked_groups.snapshot() {
+            if checkpoints
+                .get(group.telegram_id)
+                .is_some_and(|checkpoint| checkpoint.completed)
+            {
+                println!("Backfill of {} already completed, skipping", group.name);
+                continue;
+            }
+
+            let interrupted = tokio::select! {
+                _ = &mut shutdown => true,
+                result = self.backfill_group(group, &mut checkpoints) => {
+                    result?;
+                    false
+                }
+            };
+
+            checkpoints.save(&self.config.checkpoint_file)?;
+
+            if interrupted {
+                println!("Shutdown signal received, backfill checkpoint saved");
+                break;
+            }
+        }
+
+        self.telegram_account.save_session()?;
+
+        Ok(())
+    }
+
+    async fn backfill_group(
+        &self,
+        group: &TelegramGroup,
+        checkpoints: &mut BackfillCheckpoints,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut messages = self.telegram_account.client.iter_messages(group.pack());
+
+        // 1. Resume below the oldest message processed by a previous run
+        if let Some(offset_id) = checkpoints
+            .get(group.telegram_id)
+            .and_then(|checkpoint| checkpoint.offset_id)
+        {
+            println!(
+                "Resuming backfill of {} below message {}",
+                group.name, offset_id
+            );
+            messages = messages.offset_id(offset_id);
+        } else {
+            println!("Starting backfill of {}", group.name);
+        }
+
+        let mut processed = 0;
+        let mut batch = Vec::with_capacity(self.config.batch_size);
+        loop {
+            // 2. Fetch the next (older) message, waiting out any FLOOD_WAIT the client didn't sleep on
+            let message = match messages.next().await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(InvocationError::Rpc(err)) if err.code == 420 => {
+                    let wait_secs = err.value.unwrap_or(DEFAULT_FLOOD_WAIT_SECS);
+                    eprintln!(
+                        "Hit {} while backfilling {}, sleeping for {}s",
+                        err.name, group.name, wait_secs
+                    );
+                    sleep(Duration::from_secs(wait_secs as u64)).await;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            // 3. Stop once we've gone back far enough
+            if self.config.is_past_limit(message.id(), message.date()) {
+                break;
+            }
+
+            // 4. Parse in batches so their chain data is fetched together
+            batch.push(message);
+            if batch.len() >= self.config.batch_size {
+                self.process_batch(group, &mut batch, checkpoints, &mut processed)
+                    .await?;
+            }
+        }
+        self.process_batch(group, &mut batch, checkpoints, &mut processed)
+            .await?;
+
+        checkpoints.complete(group.telegram_id);
+        println!(
+            "Finished backfill of {} ({} messages)",
+            group.name, processed
+        );
+
+        Ok(())
+    }
+
+    /// Same extraction as live messages, with the Solana accounts of the whole batch
+    /// fetched together. Messages are parsed concurrently but finish in order, so the
+    /// checkpoint never skips a message that is still being parsed.
+    async fn process_batch(
+        &self,
+        group: &TelegramGroup,
+        batch: &mut Vec<Message>,
+        checkpoints: &mut BackfillCheckpoints,
+        processed: &mut usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.pipeline.prefetch(batch).await;
+
+        let mut message_ids = stream::iter(batch.drain(..))
+            .map(|message| async move {
+                let _slot = self.pipeline.acquire_slot().await;
+                let message_id = message.id();
+                self.pipeline.process(&self.telegram_account, message).await;
+                message_id
+            })
+            .buffered(self.pipeline.max_concurrent_messages());
+
+        while let Some(message_id) = message_ids.next().await {
+            checkpoints.record(group.telegram_id, message_id);
+
+            *processed += 1;
+            if processed.is_multiple_of(self.config.checkpoint_every) {
+                checkpoints.save(&self.config.checkpoint_file)?;
+                println!("Backfilled {} messages from {}", processed, group.name);
+            }
+        }
+
+        Ok(())
+    }
+}
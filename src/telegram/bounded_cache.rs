@@ -0,0 +1,113 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Map keeping only the `capacity` most recently inserted keys, evicting the oldest first.
+/// Updating a key keeps its place. Callers share it behind a `Mutex`.
+pub struct BoundedCache<K, V> {
+    capacity: usize,
+    values: HashMap<K, V>,
+    order: VecDeque<K>,
+}
+
+impl<K: Clone + Eq + Hash, V> BoundedCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            values: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.values.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.values.get_mut(key)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.values.contains_key(key)
+    }
+
+    /// Returns false if the key was already there, its value is replaced
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        if self.values.insert(key.clone(), value).is_some() {
+            return false;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.values.remove(&oldest);
+            }
+        }
+
+        true
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let value = self.values.remove(key)?;
+        self.order.retain(|other| other.borrow() != key);
+
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_the_oldest_key_when_full() {
+        let mut cache = BoundedCache::new(2);
+        assert!(cache.insert("a", 1));
+        assert!(cache.insert("b", 2));
+        assert!(cache.insert("c", 3));
+
+        assert!(!cache.contains("a"));
+        assert_eq!(cache.get("b"), Some(&2));
+        assert_eq!(cache.get("c"), Some(&3));
+    }
+
+    #[test]
+    fn updating_a_key_keeps_its_place() {
+        let mut cache = BoundedCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert!(!cache.insert("a", 10));
+        cache.insert("c", 3);
+
+        assert!(!cache.contains("a"));
+        assert_eq!(cache.get("b"), Some(&2));
+    }
+
+    #[test]
+    fn removed_keys_free_their_place() {
+        let mut cache = BoundedCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.remove("a"), Some(1));
+        cache.insert("c", 3);
+
+        assert_eq!(cache.get("b"), Some(&2));
+        assert_eq!(cache.get("c"), Some(&3));
+    }
+}
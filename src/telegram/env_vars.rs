@@ -0,0 +1,30 @@
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Settings fall back to their default when unset. A value that is set but invalid is
+// a configuration mistake, so it panics at startup instead of being silently ignored.
+
+/// Parsed value of the env var, `default` when it isn't set
+pub fn env_or<T: FromStr>(env_var: &str, default: T) -> T {
+    match env::var(env_var) {
+        Ok(value) => match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(_) => panic!("Could not parse {}: {}", env_var, value),
+        },
+        Err(_) => default,
+    }
+}
+
+/// Same as `env_or` for counts and capacities, which must be above zero
+pub fn positive_env_or(env_var: &str, default: usize) -> usize {
+    match env_or(env_var, default) {
+        0 => panic!("{} must be a positive integer", env_var),
+        value => value,
+    }
+}
+
+/// Same as `env_or` for a number of seconds
+pub fn secs_env_or(env_var: &str, default_secs: u64) -> Duration {
+    Duration::from_secs(env_or(env_var, default_secs))
+}
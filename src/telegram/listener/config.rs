@@ -0,0 +1,66 @@
+use crate::telegram::{positive_env_or, secs_env_or};
+use dotenv::dotenv;
+use std::env;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct PipelineConfig {
+    /// Comma separated sink specs, e.g. `stdout,jsonl:calls.jsonl`
+    pub sinks: Vec<String>,
+    /// How many recent messages are remembered for cross-account deduplication
+    pub dedup_capacity: usize,
+    /// How many recent calls are kept to match later edits and deletions against
+    pub call_history_capacity: usize,
+    /// How many messages are parsed at the same time, across all accounts
+    pub max_concurrent_messages: usize,
+}
+
+impl PipelineConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            sinks: env::var("CALL_SINKS")
+                .unwrap_or_else(|_| String::from("stdout"))
+                .split(',')
+                .map(|sink| sink.trim().to_string())
+                .filter(|sink| !sink.is_empty())
+                .collect(),
+            dedup_capacity: positive_env_or("DEDUP_CAPACITY", 10_000),
+            call_history_capacity: positive_env_or("CALL_HISTORY_CAPACITY", 10_000),
+            max_concurrent_messages: positive_env_or("MAX_CONCURRENT_MESSAGES", 8),
+        }
+    }
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct ListenerConfig {
+    pub reconnect_delay: Duration,
+    pub max_reconnect_delay: Duration,
+    /// How often the .env file is checked for tracked chat changes
+    pub config_poll_interval: Duration,
+}
+
+impl ListenerConfig {
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        Self {
+            reconnect_delay: secs_env_or("LISTENER_RECONNECT_DELAY_SECS", 5),
+            max_reconnect_delay: secs_env_or("LISTENER_MAX_RECONNECT_DELAY_SECS", 300),
+            config_poll_interval: secs_env_or("LISTENER_CONFIG_POLL_SECS", 10),
+        }
+    }
+}
+
+impl Default for ListenerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
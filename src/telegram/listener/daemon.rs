@@ -0,0 +1,221 @@
+use super::{CallPipeline, ListenerConfig};
+use crate::telegram::{ConfigWatcher, TelegramAccount};
+use grammers_client::{
+    types::{Chat, Message},
+    Update,
+};
+use grammers_mtsender::InvocationError;
+use std::cmp::min;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::time::{interval, sleep};
+
+/// Sent to the account's own Saved Messages to reload the tracked groups
+const RELOAD_COMMAND: &str = "/reload";
+
+/// Long-running loop that feeds live Telegram updates into the parser
+pub struct TelegramListener {
+    telegram_account: TelegramAccount,
+    pipeline: Arc<CallPipeline>,
+    config: ListenerConfig,
+}
+
+impl TelegramListener {
+    pub fn new(
+        telegram_account: TelegramAccount,
+        pipeline: Arc<CallPipeline>,
+        config: ListenerConfig,
+    ) -> Self {
+        Self {
+            telegram_account,
+            pipeline,
+            config,
+        }
+    }
+
+    /// Consumes updates until SIGINT/SIGTERM or a graceful disconnect, then saves the session
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+
+        for group in self.telegram_account.tracked_groups.snapshot() {
+            println!(
+                "[{}] Listening to {} ({})",
+                self.telegram_account.prefix, group.name, group.telegram_id
+            );
+        }
+
+        let mut reconnect_delay = self.config.reconnect_delay;
+        let mut config_watcher = ConfigWatcher::new();
+        let mut config_poll = interval(self.config.config_poll_interval);
+
+        loop {
+            tokio::select! {
+                _ = config_poll.tick() => {
+                    if config_watcher.changed() {
+                        self.reload_tracked_groups("config file changed").await;
+                    }
+                }
+                _ = &mut shutdown => {
+                    println!(
+                        "[{}] Shutdown signal received, stopping listener...",
+                        self.telegram_account.prefix
+                    );
+                    break;
+                }
+                update = self.telegram_account.client.next_update() => match update {
+                    Ok(Some(update)) => {
+                        reconnect_delay = self.config.reconnect_delay;
+                        if !self.handle_update(update, shutdown.as_mut()).await {
+                            println!(
+                                "[{}] Shutdown signal received, stopping listener...",
+                                self.telegram_account.prefix
+                            );
+                            break;
+                        }
+                    }
+                    Ok(None) => {
+                        println!(
+                            "[{}] Telegram connection closed gracefully",
+                            self.telegram_account.prefix
+                        );
+                        break;
+                    }
+                    // Network errors: the sender reconnects on the next request, so back off and retry
+                    Err(InvocationError::Read(err)) => {
+                        eprintln!(
+                            "[{}] Lost connection to Telegram: {} \nRetrying in {:?}",
+                            self.telegram_account.prefix, err, reconnect_delay
+                        );
+                        tokio::select! {
+                            _ = &mut shutdown => {
+                                println!(
+                                    "[{}] Shutdown signal received, stopping listener...",
+                                    self.telegram_account.prefix
+                                );
+                                break;
+                            }
+                            _ = sleep(reconnect_delay) => {}
+                        }
+                        reconnect_delay = min(reconnect_delay * 2, self.config.max_reconnect_delay);
+                    }
+                    Err(err) => eprintln!(
+                        "[{}] Error while fetching next update: {}",
+                        self.telegram_account.prefix, err
+                    ),
+                }
+            }
+        }
+
+        self.pipeline.wait_idle().await;
+        self.telegram_account.save_session()?;
+        println!("Session saved to {}", self.telegram_account.session_name);
+
+        Ok(())
+    }
+
+    /// Returns false if the shutdown signal arrived while waiting for a free slot
+    async fn handle_update(
+        &self,
+        update: Update,
+        shutdown: Pin<&mut impl Future<Output = ()>>,
+    ) -> bool {
+        let message = match update {
+            Update::NewMessage(message) if !message.outgoing() => message,
+            Update::NewMessage(message) if Self::is_reload_command(&message) => {
+                let count = self.reload_tracked_groups("reload command").await;
+                let reply = match count {
+                    Some(count) => format!("Reloaded, tracking {} groups", count),
+                    None => String::from("Reload failed, still tracking the previous groups"),
+                };
+                if let Err(err) = message.reply(reply).await {
+                    eprintln!("Could not reply to reload command: {}", err);
+                }
+                return true;
+            }
+            Update::MessageEdited(message) if !message.outgoing() => {
+                self.pipeline
+                    .process_edit(&self.telegram_account, message)
+                    .await;
+                return true;
+            }
+            Update::MessageDeleted(deletion) => {
+                self.pipeline
+                    .process_deletion(&self.telegram_account, &deletion)
+                    .await;
+                return true;
+            }
+            _ => return true,
+        };
+
+        // Messages are parsed concurrently, the update loop only waits for a free slot
+        let slot = tokio::select! {
+            slot = self.pipeline.acquire_slot() => slot,
+            _ = shutdown => return false,
+        };
+        // The guard and the slot are released when the task ends, even if parsing panics
+        let in_flight = self.pipeline.start(&self.telegram_account, &message);
+        let pipeline = self.pipeline.clone();
+        let account = self.telegram_account.clone();
+        tokio::spawn(async move {
+            pipeline.process(&account, message).await;
+            drop(in_flight);
+            drop(slot);
+        });
+
+        true
+    }
+
+    /// Admin commands are only accepted from the account itself, in its Saved Messages
+    fn is_reload_command(message: &Message) -> bool {
+        message.outgoing()
+            && matches!(message.chat(), Chat::User(user) if user.is_self())
+            && message.text().trim() == RELOAD_COMMAND
+    }
+
+    /// Returns the new number of tracked groups, or None if the reload failed
+    async fn reload_tracked_groups(&self, reason: &str) -> Option<usize> {
+        let prefix = &self.telegram_account.prefix;
+        println!("[{}] Reloading tracked groups: {}", prefix, reason);
+
+        match self.telegram_account.reload_tracked_groups().await {
+            Ok(count) => {
+                println!("[{}] Now tracking {} groups", prefix, count);
+                Some(count)
+            }
+            Err(err) => {
+                eprintln!(
+                    "[{}] Reload failed, keeping the current groups: {}",
+                    prefix, err
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Resolves on the first SIGINT (Ctrl+C) or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,70 @@
+use super::CallKey;
+use crate::telegram::BoundedCache;
+use grammers_client::types::Message;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Remembers the most recent messages so a message seen by several accounts
+/// in the same chat is only processed once
+pub struct MessageDeduplicator {
+    /// Key of the account that saw each message first
+    seen: Mutex<BoundedCache<SeenKey, CallKey>>,
+}
+
+/// What identifies a message across accounts
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum SeenKey {
+    /// Channels and megagroups share message ids between accounts
+    Channel { chat_id: i64, message_id: i32 },
+    /// Basic group message ids are counted per account, so the same id from two accounts
+    /// refers to two different messages. What both accounts see is the sender, date and text.
+    BasicGroup {
+        chat_id: i64,
+        sender_id: Option<i64>,
+        timestamp: i64,
+        text_hash: u64,
+    },
+}
+
+impl SeenKey {
+    fn from_message(message: &Message) -> Self {
+        let chat = message.chat();
+        if chat.pack().is_channel() {
+            return Self::Channel {
+                chat_id: chat.id(),
+                message_id: message.id(),
+            };
+        }
+
+        let mut hasher = DefaultHasher::new();
+        message.text().hash(&mut hasher);
+        Self::BasicGroup {
+            chat_id: chat.id(),
+            sender_id: message.sender().map(|sender| sender.id()),
+            timestamp: message.date().timestamp(),
+            text_hash: hasher.finish(),
+        }
+    }
+}
+
+impl MessageDeduplicator {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            seen: Mutex::new(BoundedCache::new(capacity)),
+        }
+    }
+
+    /// Returns None the first time a message is seen, remembering it under `key`.
+    /// Later sightings, from other accounts too, return the key of the first one.
+    pub fn earlier_sighting(&self, message: &Message, key: &CallKey) -> Option<CallKey> {
+        let seen_key = SeenKey::from_message(message);
+        let mut seen = self.seen.lock().unwrap();
+        if let Some(original) = seen.get(&seen_key) {
+            return Some(original.clone());
+        }
+        seen.insert(seen_key, key.clone());
+
+        None
+    }
+}
@@ -0,0 +1,101 @@
+use crate::telegram::{BoundedCache, CallEvent, ParseResult};
+use std::sync::Mutex;
+
+/// Where a message id is unique. Channel and megagroup ids are shared by every account,
+/// while basic group and private chat ids are counted per account, and their deletion
+/// updates don't even say which chat the message was in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MessageScope {
+    Channel(i64),
+    Account(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallKey {
+    pub scope: MessageScope,
+    pub message_id: i32,
+}
+
+/// Remembers the latest revision of the most recent calls, so edits and deletions
+/// arriving later can be matched back to the call they change.
+/// It lives in memory only, edits and deletions of calls it no longer has are lost.
+pub struct CallHistory {
+    calls: Mutex<RecordedCalls>,
+}
+
+struct RecordedCalls {
+    latest: BoundedCache<CallKey, ParseResult>,
+    /// Basic group messages seen by a second account, by that account's key, point to
+    /// the key of the first sighting which the call is recorded under
+    aliases: BoundedCache<CallKey, CallKey>,
+}
+
+impl RecordedCalls {
+    fn resolve(&self, key: &CallKey) -> CallKey {
+        self.aliases.get(key).unwrap_or(key).clone()
+    }
+}
+
+impl CallHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            calls: Mutex::new(RecordedCalls {
+                latest: BoundedCache::new(capacity),
+                aliases: BoundedCache::new(capacity),
+            }),
+        }
+    }
+
+    /// The key the call is recorded under, which is another account's for a message
+    /// that account saw first
+    pub fn resolve(&self, key: &CallKey) -> CallKey {
+        self.calls.lock().unwrap().resolve(key)
+    }
+
+    /// Makes `key` refer to the message first seen under `original`
+    pub fn alias(&self, key: CallKey, original: CallKey) {
+        if key != original {
+            self.calls.lock().unwrap().aliases.insert(key, original);
+        }
+    }
+
+    pub fn contains(&self, key: &CallKey) -> bool {
+        let calls = self.calls.lock().unwrap();
+        calls.latest.contains(&calls.resolve(key))
+    }
+
+    /// Stores `parse_result` as the latest revision of the call, evicting the oldest call when full
+    pub fn record(&self, key: CallKey, parse_result: ParseResult) {
+        self.calls.lock().unwrap().latest.insert(key, parse_result);
+    }
+
+    /// Replaces the latest revision of the call with what `revise` makes of it, if anything.
+    /// The history stays locked in between, so the same edit handled twice at once
+    /// is only revised once and concurrent revisions are numbered one after the other.
+    pub fn revise<F>(&self, key: &CallKey, revise: F) -> Option<ParseResult>
+    where
+        F: FnOnce(Option<&ParseResult>) -> Option<ParseResult>,
+    {
+        let mut calls = self.calls.lock().unwrap();
+        let key = calls.resolve(key);
+        let parse_result = revise(calls.latest.get(&key))?;
+        calls.latest.insert(key, parse_result.clone());
+
+        Some(parse_result)
+    }
+
+    /// Marks a recorded call as deleted and returns it, or None if it is unknown or already deleted
+    pub fn mark_deleted(&self, key: &CallKey) -> Option<ParseResult> {
+        let mut calls = self.calls.lock().unwrap();
+        let key = calls.resolve(key);
+        let parse_result = calls.latest.get_mut(&key)?;
+        if parse_result.call.deleted {
+            return None;
+        }
+
+        parse_result.call.deleted = true;
+        parse_result.call.event = CallEvent::Deleted;
+
+        Some(parse_result.clone())
+    }
+}
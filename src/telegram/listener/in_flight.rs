@@ -0,0 +1,137 @@
+use super::CallKey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Messages currently being parsed, so an edit or deletion of one of them can wait
+/// for it to be recorded without waiting for every other message.
+/// Accounts seeing the same message each start and finish it once.
+#[derive(Default)]
+pub struct InFlightMessages {
+    messages: Mutex<HashMap<CallKey, InFlightMessage>>,
+}
+
+struct InFlightMessage {
+    /// How many sightings of the message haven't finished yet
+    pending: usize,
+    notify: Arc<Notify>,
+}
+
+impl InFlightMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sighting is finished when the returned guard is dropped
+    pub fn start(self: &Arc<Self>, key: CallKey) -> InFlightGuard {
+        self.messages
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| InFlightMessage {
+                pending: 0,
+                notify: Arc::new(Notify::new()),
+            })
+            .pending += 1;
+
+        InFlightGuard {
+            messages: self.clone(),
+            key,
+        }
+    }
+
+    /// Wakes everything waiting on the message once its last sighting is finished
+    fn finish(&self, key: &CallKey) {
+        let mut messages = self.messages.lock().unwrap();
+        let message = match messages.get_mut(key) {
+            Some(message) => message,
+            None => return,
+        };
+        message.pending -= 1;
+        if message.pending == 0 {
+            if let Some(message) = messages.remove(key) {
+                message.notify.notify_waiters();
+            }
+        }
+    }
+
+    /// Returns once the message is no longer being parsed, immediately if it isn't
+    pub async fn wait(&self, key: &CallKey) {
+        loop {
+            let notify = match self.messages.lock().unwrap().get(key) {
+                Some(message) => message.notify.clone(),
+                None => return,
+            };
+            // Enabled before checking again, so a `finish` in between still wakes it
+            let notified = notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if !self.messages.lock().unwrap().contains_key(key) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Finishes a sighting when dropped, so a message whose parsing panicked
+/// doesn't keep its edits and deletions waiting forever
+pub struct InFlightGuard {
+    messages: Arc<InFlightMessages>,
+    key: CallKey,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.messages.finish(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telegram::MessageScope;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    fn key(message_id: i32) -> CallKey {
+        CallKey {
+            scope: MessageScope::Channel(1),
+            message_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_until_every_sighting_is_dropped() {
+        let messages = Arc::new(InFlightMessages::new());
+        let first = messages.start(key(1));
+        let second = messages.start(key(1));
+        let _other = messages.start(key(2));
+
+        drop(first);
+        let key = key(1);
+        let wait = messages.wait(&key);
+        tokio::pin!(wait);
+        assert!(timeout(Duration::from_millis(10), wait.as_mut())
+            .await
+            .is_err());
+
+        drop(second);
+        assert!(timeout(Duration::from_millis(10), wait).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn a_panicking_task_finishes_its_sighting() {
+        let messages = Arc::new(InFlightMessages::new());
+        let guard = messages.start(key(1));
+        let task = tokio::spawn(async move {
+            let _guard = guard;
+            panic!("parser panicked");
+        });
+        assert!(task.await.is_err());
+
+        assert!(timeout(Duration::from_millis(10), messages.wait(&key(1)))
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,15 @@
+mod config;
+mod daemon;
+mod dedup;
+mod history;
+mod in_flight;
+mod pipeline;
+mod sink;
+
+pub use config::*;
+pub use daemon::*;
+pub use dedup::*;
+pub use history::*;
+pub use in_flight::*;
+pub use pipeline::*;
+pub use sink::*;
@@ -0,0 +1,236 @@
+use super::{
+    build_sinks, CallHistory, CallKey, CallSink, InFlightGuard, InFlightMessages,
+    MessageDeduplicator, MessageScope, PipelineConfig,
+};
+use crate::telegram::{CallEvent, DefaultParser, MessageParser, ParseResult, TelegramAccount};
+use grammers_client::types::{Message, MessageDeletion};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Extraction pipeline shared by the live listener and the backfill:
+/// every message goes through the parser and each result is handed to all sinks.
+/// One pipeline is shared by all accounts, so messages seen twice are dropped here.
+pub struct CallPipeline<P = DefaultParser> {
+    parser: P,
+    sinks: Vec<Box<dyn CallSink>>,
+    deduplicator: MessageDeduplicator,
+    history: CallHistory,
+    in_flight: Arc<InFlightMessages>,
+    /// One permit per message being parsed
+    slots: Arc<Semaphore>,
+    max_concurrent_messages: usize,
+}
+
+impl<P: MessageParser> CallPipeline<P> {
+    pub fn new(parser: P, config: PipelineConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let sinks = build_sinks(&config.sinks)?;
+        let deduplicator = MessageDeduplicator::new(config.dedup_capacity);
+        let history = CallHistory::new(config.call_history_capacity);
+
+        Ok(Self {
+            parser,
+            sinks,
+            deduplicator,
+            history,
+            in_flight: Arc::new(InFlightMessages::new()),
+            slots: Arc::new(Semaphore::new(config.max_concurrent_messages)),
+            max_concurrent_messages: config.max_concurrent_messages,
+        })
+    }
+
+    pub fn max_concurrent_messages(&self) -> usize {
+        self.max_concurrent_messages
+    }
+
+    /// Waits until fewer than `max_concurrent_messages` messages are being parsed.
+    /// Hold the permit while processing a message, dropping it frees the slot.
+    pub async fn acquire_slot(&self) -> OwnedSemaphorePermit {
+        self.slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("pipeline slots are never closed")
+    }
+
+    /// Waits for every message being parsed to finish
+    pub async fn wait_idle(&self) {
+        let _all_slots = self
+            .slots
+            .acquire_many(self.max_concurrent_messages as u32)
+            .await
+            .expect("pipeline slots are never closed");
+    }
+
+    /// Fetches the chain data a batch of messages needs in as few requests as possible,
+    /// before they are processed
+    pub async fn prefetch(&self, messages: &[Message]) {
+        self.parser.prefetch(messages).await;
+    }
+
+    /// Marks a message as being processed before its `process` task is spawned,
+    /// so edits and deletions handled in the meantime wait for it.
+    /// It stays marked until the returned guard is dropped, keep it for the whole task.
+    pub fn start(&self, account: &TelegramAccount, message: &Message) -> InFlightGuard {
+        self.in_flight.start(Self::message_key(account, message))
+    }
+
+    /// `account` is the one that received the message
+    pub async fn process(&self, account: &TelegramAccount, message: Message) {
+        let key = Self::message_key(account, &message);
+        match self.deduplicator.earlier_sighting(&message, &key) {
+            // Edits and deletions this account sees are applied to the first sighting's call
+            Some(original) => self.history.alias(key, original),
+            None => {
+                if let Some(mut parse_result) = self.parse(account, message).await {
+                    self.link_forward_origin(&mut parse_result);
+                    self.emit(&parse_result);
+                    self.history.record(key, parse_result);
+                }
+            }
+        }
+    }
+
+    /// Re-runs extraction on an edited message and emits a revision if the called tokens changed.
+    /// Edits that add an address to a message that had none count as revisions too.
+    pub async fn process_edit(&self, account: &TelegramAccount, message: Message) {
+        // The edited message may still be parsing, it has to be recorded first.
+        // The account's own sighting is waited for before the first one it may point to.
+        let key = Self::message_key(account, &message);
+        self.in_flight.wait(&key).await;
+        let key = self.history.resolve(&key);
+        self.in_flight.wait(&key).await;
+
+        let message_text = message.text().to_string();
+        let edited_at = message.edit_date().map(|date| date.to_string());
+        let mut parse_result = match self.parser.parse(&account.client, message).await {
+            Ok(Some(parse_result)) => parse_result,
+            // No token left in the message: the call is retracted
+            Ok(None) => {
+                self.retract(&key, message_text, edited_at);
+                return;
+            }
+            Err(err) => {
+                eprintln!("Error while parsing edited message: {}", err);
+                return;
+            }
+        };
+
+        // Edits keeping the same tokens (typo fixes, added comments) are not revisions.
+        // The same edit seen by a second account keeps them too once the first one is recorded.
+        self.link_forward_origin(&mut parse_result);
+        let revision = self.history.revise(&key, |previous| {
+            let previous_addresses = previous
+                .map(ParseResult::accepted_addresses)
+                .unwrap_or_default();
+            if previous.is_some() && previous_addresses == parse_result.accepted_addresses() {
+                return None;
+            }
+
+            parse_result.call.event = CallEvent::Revised;
+            parse_result.call.revision = previous.map_or(1, |call| call.call.revision + 1);
+            parse_result.call.previous_addresses = previous_addresses;
+            Some(parse_result)
+        });
+
+        if let Some(parse_result) = revision {
+            self.emit(&parse_result);
+        }
+    }
+
+    /// Emits a revision without tokens when an edit removed every address of a recorded call.
+    /// Unknown calls and calls already retracted, by the same edit seen by a second account
+    /// for instance, are left as they are.
+    fn retract(&self, key: &CallKey, message_text: String, edited_at: Option<String>) {
+        let revision = self.history.revise(key, |previous| {
+            let mut parse_result = previous?.clone();
+            let previous_addresses = parse_result.accepted_addresses();
+            if previous_addresses.is_empty() {
+                return None;
+            }
+
+            parse_result.token_calls = Vec::new();
+            parse_result.call.event = CallEvent::Revised;
+            parse_result.call.revision += 1;
+            parse_result.call.previous_addresses = previous_addresses;
+            parse_result.call.message_text = message_text;
+            parse_result.call.edited_at = edited_at;
+            parse_result.call.market_data_providers = Vec::new();
+            Some(parse_result)
+        });
+
+        if let Some(parse_result) = revision {
+            self.emit(&parse_result);
+        }
+    }
+
+    /// Emits the latest revision of every deleted call, marked as deleted.
+    /// Deletions are matched against the in-memory `CallHistory` only, so calls evicted from it
+    /// (see `CALL_HISTORY_CAPACITY`) or seen before a restart are never marked as deleted.
+    pub async fn process_deletion(&self, account: &TelegramAccount, deletion: &MessageDeletion) {
+        let scope = match deletion.channel_id() {
+            Some(channel_id) => MessageScope::Channel(channel_id),
+            None => MessageScope::Account(account.prefix.clone()),
+        };
+
+        for &message_id in deletion.messages() {
+            let key = CallKey {
+                scope: scope.clone(),
+                message_id,
+            };
+            self.in_flight.wait(&key).await;
+            let key = self.history.resolve(&key);
+            self.in_flight.wait(&key).await;
+            if let Some(parse_result) = self.history.mark_deleted(&key) {
+                self.emit(&parse_result);
+            }
+        }
+    }
+
+    /// Links a forwarded call to the original one when the source is tracked and was seen
+    fn link_forward_origin(&self, parse_result: &mut ParseResult) {
+        let origin = match parse_result.call.forwarded_from.as_mut() {
+            Some(origin) if origin.is_tracked_source => origin,
+            _ => return,
+        };
+
+        if let (Some(channel_id), Some(message_id)) = (origin.channel_id, origin.message_id) {
+            let key = CallKey {
+                scope: MessageScope::Channel(channel_id),
+                message_id,
+            };
+            origin.original_recorded = self.history.contains(&key);
+        }
+    }
+
+    async fn parse(&self, account: &TelegramAccount, message: Message) -> Option<ParseResult> {
+        match self.parser.parse(&account.client, message).await {
+            Ok(parse_result) => parse_result,
+            Err(err) => {
+                eprintln!("Error while parsing message: {}", err);
+                None
+            }
+        }
+    }
+
+    fn emit(&self, parse_result: &ParseResult) {
+        for sink in &self.sinks {
+            if let Err(err) = sink.handle(parse_result) {
+                eprintln!("Sink {} failed to handle call: {}", sink.name(), err);
+            }
+        }
+    }
+
+    fn message_key(account: &TelegramAccount, message: &Message) -> CallKey {
+        let chat = message.chat();
+        let scope = if chat.pack().is_channel() {
+            MessageScope::Channel(chat.id())
+        } else {
+            MessageScope::Account(account.prefix.clone())
+        };
+
+        CallKey {
+            scope,
+            message_id: message.id(),
+        }
+    }
+}
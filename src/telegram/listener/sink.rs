@@ -0,0 +1,73 @@
+use crate::telegram::ParseResult;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Destination for every successfully parsed call
+pub trait CallSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn handle(&self, parse_result: &ParseResult) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Prints parse results to stdout
+pub struct StdoutSink;
+
+impl CallSink for StdoutSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+
+    fn handle(&self, parse_result: &ParseResult) -> Result<(), Box<dyn std::error::Error>> {
+        println!("parse_result: {:?}", parse_result);
+        Ok(())
+    }
+}
+
+/// Appends parse results as one JSON object per line
+pub struct JsonLinesSink {
+    path: String,
+    file: Mutex<File>,
+}
+
+impl JsonLinesSink {
+    pub fn new(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            path: path.to_string(),
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl CallSink for JsonLinesSink {
+    fn name(&self) -> &str {
+        &self.path
+    }
+
+    fn handle(&self, parse_result: &ParseResult) -> Result<(), Box<dyn std::error::Error>> {
+        let line = serde_json::to_string(parse_result)?;
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Builds sinks from specs such as `stdout` or `jsonl:calls.jsonl`
+pub fn build_sinks(specs: &[String]) -> Result<Vec<Box<dyn CallSink>>, Box<dyn std::error::Error>> {
+    let mut sinks: Vec<Box<dyn CallSink>> = Vec::new();
+
+    for spec in specs {
+        let sink: Box<dyn CallSink> = match spec.split_once(':') {
+            None if spec == "stdout" => Box::new(StdoutSink),
+            Some(("jsonl", path)) => Box::new(JsonLinesSink::new(path)?),
+            _ => return Err(format!("Unknown sink: {}", spec).into()),
+        };
+        sinks.push(sink);
+    }
+
+    Ok(sinks)
+}
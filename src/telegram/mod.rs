@@ -1,5 +1,13 @@
 mod accounts;
+mod backfill;
+mod bounded_cache;
+mod env_vars;
+mod listener;
 mod parser;
 
 pub use accounts::*;
+pub use backfill::*;
+pub use bounded_cache::*;
+pub use env_vars::*;
+pub use listener::*;
 pub use parser::*;
@@ -4,21 +4,21 @@ use std::collections::HashMap;
 
 /* Birdeye response datas */
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SolanaTokenOverviewResponse {
     pub data: SolanaTokenOverviewData,
     pub success: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SolanaTokenOverviewData {
-    Data(SolanaTokenOverview),
+    Data(Box<SolanaTokenOverview>),
     Empty(HashMap<String, serde_json::Value>),
 }
 
 /// Data field from the /token_overview response from Birdeye
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SolanaTokenOverview {
     pub address: String,
@@ -368,16 +368,16 @@ pub struct SolanaTokenOverview {
     pub number_markets: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Extensions {
     #[serde(flatten)]
     pub properties: HashMap<String, Option<String>>,
 }
 
-/** Formatted data by personal choice*/
+/* Formatted data by personal choice */
 
 /// Solana Token data from 1 Call.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SolanaTokenCall {
     pub liquidity: Option<f64>,
     pub price: f64,
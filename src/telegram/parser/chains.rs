@@ -0,0 +1,121 @@
+use regex::Regex;
+use reqwest::Url;
+use serde::Serialize;
+use std::cmp::Reverse;
+
+/// Chains calls are tracked on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Chain {
+    Solana,
+    Ethereum,
+    Base,
+    Bsc,
+}
+
+/// Chains sharing the 0x address format, in the order they are tried when nothing hints one
+pub const EVM_CHAINS: [Chain; 3] = [Chain::Ethereum, Chain::Base, Chain::Bsc];
+
+impl Chain {
+    /// Chain id as used by Dexscreener and the `x-chain` header of Birdeye
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Solana => "solana",
+            Self::Ethereum => "ethereum",
+            Self::Base => "base",
+            Self::Bsc => "bsc",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "solana" | "sol" => Some(Self::Solana),
+            "ethereum" | "eth" | "ether" => Some(Self::Ethereum),
+            "base" => Some(Self::Base),
+            "bsc" | "bnb" => Some(Self::Bsc),
+            _ => None,
+        }
+    }
+
+    /// Chain named by a link, either through an explorer domain
+    /// or a chain segment like dexscreener.com/base/<pair>
+    pub fn from_url(url: &Url) -> Option<Self> {
+        let host = url.host_str()?;
+        let explorers = [
+            ("etherscan.io", Self::Ethereum),
+            ("basescan.org", Self::Base),
+            ("bscscan.com", Self::Bsc),
+            ("solscan.io", Self::Solana),
+        ];
+        if let Some((_, chain)) = explorers
+            .iter()
+            .find(|(domain, _)| host == *domain || host.ends_with(&format!(".{}", domain)))
+        {
+            return Some(*chain);
+        }
+
+        let path_chain = url
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .find_map(Self::from_id);
+        path_chain.or_else(|| {
+            url.query_pairs()
+                .find_map(|(_, value)| Self::from_id(&value))
+        })
+    }
+}
+
+/// Infers the chain of 0x addresses from the words around them, e.g. "#base", "erc20", "bsc"
+pub struct ChainDetector {
+    hints: Vec<(Chain, Regex)>,
+}
+
+impl ChainDetector {
+    pub fn new() -> Self {
+        let hints = [
+            (
+                Chain::Ethereum,
+                r"(?i)(#eth\b|\$eth\b|\beth chain\b|\bethereum\b|\berc-?20\b|\bon eth\b|\betherscan\b|\buniswap\b)",
+            ),
+            (
+                Chain::Base,
+                r"(?i)(#base\b|\bon base\b|\bbase ?chain\b|\bbasescan\b|\baerodrome\b)",
+            ),
+            (
+                Chain::Bsc,
+                r"(?i)(#bsc\b|\bbsc\b|\bbnb chain\b|\bbep-?20\b|\bbscscan\b|\bpancakeswap\b)",
+            ),
+        ];
+
+        Self {
+            hints: hints
+                .into_iter()
+                .map(|(chain, pattern)| (chain, Regex::new(pattern).unwrap()))
+                .collect(),
+        }
+    }
+
+    /// The EVM chain mentioned most often in the text, None if there are none or a tie
+    pub fn detect(&self, text: &str) -> Option<Chain> {
+        let mut counts: Vec<(Chain, usize)> = self
+            .hints
+            .iter()
+            .map(|(chain, regex)| (*chain, regex.find_iter(text).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        counts.sort_by_key(|(_, count)| Reverse(*count));
+
+        match counts.as_slice() {
+            [(chain, _)] => Some(*chain),
+            [(chain, first), (_, second), ..] if first > second => Some(*chain),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ChainDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
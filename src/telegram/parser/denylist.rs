@@ -0,0 +1,107 @@
+use super::{
+    METADATA_PROGRAM, METEORA_DLMM_PROGRAM, METEORA_DYNAMIC_PROGRAM, ORCA_WHIRLPOOL_PROGRAM,
+    PUMP_FUN_PROGRAM, QUOTE_MINTS, RAYDIUM_AMM_V4_PROGRAM, RAYDIUM_CLMM_PROGRAM,
+    RAYDIUM_CPMM_PROGRAM,
+};
+use dotenv::dotenv;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+
+/// Programs people paste along with calls, none of them can be a token
+const PROGRAMS: [&str; 16] = [
+    "11111111111111111111111111111111",             // System program
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",  // Token program
+    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",  // Token-2022
+    "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", // Associated token account program
+    "ComputeBudget111111111111111111111111111111",  // Compute budget
+    "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",  // Memo
+    "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4",  // Jupiter v6
+    "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1", // Raydium AMM v4 authority
+    METADATA_PROGRAM,
+    PUMP_FUN_PROGRAM,
+    RAYDIUM_AMM_V4_PROGRAM,
+    RAYDIUM_CPMM_PROGRAM,
+    RAYDIUM_CLMM_PROGRAM,
+    ORCA_WHIRLPOOL_PROGRAM,
+    METEORA_DLMM_PROGRAM,
+    METEORA_DYNAMIC_PROGRAM,
+];
+
+/// Native wrappers and stablecoins, quoted in pairs but never the token being called
+const EVM_STABLE_MINTS: [&str; 7] = [
+    "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", // WETH, Ethereum
+    "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", // USDC, Ethereum
+    "0xdac17f958d2ee523a2206206994597c13d831ec7", // USDT, Ethereum
+    "0x4200000000000000000000000000000000000006", // WETH, Base
+    "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913", // USDC, Base
+    "0xbb4cdb9cbd36b01bd1cbaebf2de08d9173bc095c", // WBNB, BSC
+    "0x55d398326f99059ff775485246999027b3197955", // USDT, BSC
+];
+
+/// Why a candidate was skipped without looking it up
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// A well-known program id
+    Program,
+    /// Wrapped SOL/ETH/BNB or a stablecoin
+    StableMint,
+    /// Listed in `ADDRESS_DENYLIST`, e.g. CEX hot wallets or trading bots
+    Configured,
+}
+
+/// Addresses that are never calls, checked before any RPC or API request
+pub struct Denylist {
+    /// Keyed by address, lowercased for 0x addresses since those are case-insensitive
+    addresses: HashMap<String, SkipReason>,
+}
+
+impl Denylist {
+    /// Built-in programs and stable mints, plus the comma separated `ADDRESS_DENYLIST`
+    pub fn new() -> Self {
+        dotenv().ok();
+
+        let mut denylist = Self {
+            addresses: HashMap::new(),
+        };
+
+        if let Ok(configured) = env::var("ADDRESS_DENYLIST") {
+            for address in configured.split(',') {
+                denylist.insert(address.trim(), SkipReason::Configured);
+            }
+        }
+        for address in PROGRAMS {
+            denylist.insert(address, SkipReason::Program);
+        }
+        for address in QUOTE_MINTS.into_iter().chain(EVM_STABLE_MINTS) {
+            denylist.insert(address, SkipReason::StableMint);
+        }
+
+        denylist
+    }
+
+    pub fn check(&self, address: &str) -> Option<SkipReason> {
+        self.addresses.get(&Self::normalize(address)).copied()
+    }
+
+    fn insert(&mut self, address: &str, reason: SkipReason) {
+        if !address.is_empty() {
+            self.addresses.insert(Self::normalize(address), reason);
+        }
+    }
+
+    fn normalize(address: &str) -> String {
+        if address.starts_with("0x") {
+            address.to_lowercase()
+        } else {
+            address.to_string()
+        }
+    }
+}
+
+impl Default for Denylist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 /// Response from: https://api.dexscreener.com/latest/dex/pairs/:chainId/:pairAddresses
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DexscreenerPairsResponse {
     pub schema_version: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -10,7 +11,7 @@ pub struct DexscreenerPairsResponse {
     pub pairs: Option<Vec<DexscreenerPair>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DexscreenerPair {
     pub chain_id: String,
@@ -33,19 +34,19 @@ pub struct DexscreenerPair {
     pub pair_created_at: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerToken {
     pub address: String,
     pub name: String,
     pub symbol: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerQuoteToken {
     pub symbol: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerTransactions {
     pub m5: DexscreenerTransactionDetail,
     pub h1: DexscreenerTransactionDetail,
@@ -53,13 +54,13 @@ pub struct DexscreenerTransactions {
     pub h24: DexscreenerTransactionDetail,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerTransactionDetail {
     pub buys: i32,
     pub sells: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerVolume {
     pub m5: f64,
     pub h1: f64,
@@ -67,7 +68,7 @@ pub struct DexscreenerVolume {
     pub h24: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerPriceChange {
     pub m5: f64,
     pub h1: f64,
@@ -75,10 +76,52 @@ pub struct DexscreenerPriceChange {
     pub h24: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexscreenerLiquidity {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usd: Option<f64>,
     pub base: f64,
     pub quote: f64,
 }
+
+/// Response from: https://api.dexscreener.com/latest/dex/search?q=:query
+/// and https://api.dexscreener.com/latest/dex/tokens/:tokenAddresses
+/// Only the fields needed to pick a pair and price it, these results are often incomplete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DexscreenerSearchResponse {
+    #[serde(default)]
+    pub pairs: Option<Vec<DexscreenerSearchPair>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DexscreenerSearchPair {
+    pub chain_id: String,
+    pub pair_address: String,
+    pub base_token: DexscreenerToken,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liquidity: Option<DexscreenerSearchLiquidity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair_created_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_usd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_cap: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<DexscreenerSearchPeriods>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_change: Option<DexscreenerSearchPeriods>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DexscreenerSearchLiquidity {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usd: Option<f64>,
+}
+
+/// Volume or price change of a search pair, only the 24h window is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DexscreenerSearchPeriods {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h24: Option<f64>,
+}
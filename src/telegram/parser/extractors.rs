@@ -0,0 +1,565 @@
+use super::{
+    link_addresses, message_urls, CandidatePosition, Chain, ChainDetector, TickerExtractor,
+    TickerMention, EVM_CHAINS,
+};
+use grammers_client::types::Message;
+use regex::Regex;
+
+/// Bare addresses in the text are sometimes wallets or pairs pasted next to the CA
+const TEXT_ADDRESS_SCORE: f64 = 0.9;
+/// Links to token sites almost always point to the called token
+const LINK_ADDRESS_SCORE: f64 = 0.95;
+/// Addresses explicitly labeled as the contract
+const LABELED_ADDRESS_SCORE: f64 = 1.0;
+/// Addresses where a call bot's layout puts the token
+const CALL_BOT_ADDRESS_SCORE: f64 = 1.0;
+
+const SOLANA_ADDRESS_PATTERN: &str = r"[1-9A-HJ-NP-Za-km-z]{32,44}";
+const EVM_ADDRESS_PATTERN: &str = r"\b0x[a-fA-F0-9]{40}\b";
+
+/// What extractors get to see of a message, so they can run without a Telegram client
+#[derive(Debug, Clone)]
+pub struct MessageContent {
+    pub chat_id: i64,
+    pub text: String,
+    /// Links of the url and text url entities
+    pub urls: Vec<String>,
+}
+
+impl MessageContent {
+    pub fn from_message(message: &Message) -> Self {
+        Self {
+            chat_id: message.chat().id(),
+            text: message.text().to_string(),
+            urls: message_urls(message),
+        }
+    }
+}
+
+/// What a candidate points to
+#[derive(Debug, Clone, PartialEq)]
+pub enum CandidateValue {
+    /// A token, pair, pool or curve address, or anything else looking like one
+    Address(String),
+    /// A symbol to search for, only used when the message has no address
+    Ticker(TickerMention),
+}
+
+/// Something an extractor found in a message, before it is resolved
+#[derive(Debug, Clone)]
+pub struct ScoredCandidate {
+    pub value: CandidateValue,
+    pub position: CandidatePosition,
+    /// Solana for base58 addresses and tickers, the inferred chain if any for 0x addresses
+    pub chain: Option<Chain>,
+    /// How likely the extractor thinks this is the called token, between 0 and 1
+    pub score: f64,
+}
+
+impl ScoredCandidate {
+    fn address(
+        address: &str,
+        position: CandidatePosition,
+        chain: Option<Chain>,
+        score: f64,
+    ) -> Self {
+        Self {
+            value: CandidateValue::Address(address.to_string()),
+            position,
+            chain,
+            score,
+        }
+    }
+
+    /// Same address (0x addresses are case-insensitive) or same ticker
+    pub fn is_same(&self, other: &Self) -> bool {
+        match (&self.value, &other.value) {
+            (CandidateValue::Address(address), CandidateValue::Address(other)) => {
+                address.eq_ignore_ascii_case(other)
+            }
+            (CandidateValue::Ticker(mention), CandidateValue::Ticker(other)) => {
+                mention.symbol == other.symbol
+            }
+            _ => false,
+        }
+    }
+}
+
+/// One stage of candidate extraction. Add one to `DefaultParser::with_extractors`
+/// to support a group's own call format.
+pub trait CandidateExtractor: Send + Sync {
+    /// Chats this extractor runs in, all of them by default
+    fn applies_to(&self, _chat_id: i64) -> bool {
+        true
+    }
+
+    fn candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate>;
+}
+
+/// The extractors `DefaultParser::new` uses. Candidates keep the position of the first
+/// extractor that found them, so text addresses stay in message order.
+pub fn default_extractors() -> Vec<Box<dyn CandidateExtractor>> {
+    vec![
+        Box::new(AddressExtractor::new()),
+        Box::new(LabeledAddressExtractor::new()),
+        Box::new(CallBotExtractor::new()),
+        Box::new(LinkExtractor::new()),
+        Box::new(TickerExtractor::new()),
+    ]
+}
+
+/// Runs every extractor that applies to the chat. Candidates found more than once
+/// keep their first position and their best score.
+pub fn run_extractors(
+    extractors: &[Box<dyn CandidateExtractor>],
+    content: &MessageContent,
+) -> Vec<ScoredCandidate> {
+    let mut candidates: Vec<ScoredCandidate> = Vec::new();
+
+    for extractor in extractors {
+        if !extractor.applies_to(content.chat_id) {
+            continue;
+        }
+
+        for candidate in extractor.candidates(content) {
+            match candidates
+                .iter_mut()
+                .find(|other| other.is_same(&candidate))
+            {
+                Some(other) => other.score = other.score.max(candidate.score),
+                None => candidates.push(candidate),
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Chain of a 0x address given the EVM chain inferred from the message, Solana otherwise.
+/// The format of the address always beats the context.
+fn address_chain(address: &str, context_chain: Option<Chain>) -> Option<Chain> {
+    if address.starts_with("0x") {
+        context_chain.filter(|chain| EVM_CHAINS.contains(chain))
+    } else {
+        Some(Chain::Solana)
+    }
+}
+
+/// Solana and EVM addresses written in the message text
+pub struct AddressExtractor {
+    solana_regex: Regex,
+    evm_regex: Regex,
+    chain_detector: ChainDetector,
+}
+
+impl AddressExtractor {
+    pub fn new() -> Self {
+        Self {
+            solana_regex: Regex::new(SOLANA_ADDRESS_PATTERN).unwrap(),
+            evm_regex: Regex::new(EVM_ADDRESS_PATTERN).unwrap(),
+            chain_detector: ChainDetector::new(),
+        }
+    }
+}
+
+impl CandidateExtractor for AddressExtractor {
+    fn candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate> {
+        let text = content.text.as_str();
+        let context_chain = self.chain_detector.detect(text);
+        let mut candidates = Vec::new();
+
+        // 1. 0x addresses, their chain comes from the context
+        let evm_matches: Vec<regex::Match> = self.evm_regex.find_iter(text).collect();
+        for mat in &evm_matches {
+            candidates.push(ScoredCandidate::address(
+                mat.as_str(),
+                CandidatePosition::Text {
+                    start: mat.start(),
+                    end: mat.end(),
+                },
+                context_chain,
+                TEXT_ADDRESS_SCORE,
+            ));
+        }
+
+        // 2. Solana addresses. Hex digits are valid base58,
+        // so matches inside 0x addresses are skipped
+        for mat in self.solana_regex.find_iter(text) {
+            if evm_matches
+                .iter()
+                .any(|evm| mat.start() < evm.end() && evm.start() < mat.end())
+            {
+                continue;
+            }
+
+            candidates.push(ScoredCandidate::address(
+                mat.as_str(),
+                CandidatePosition::Text {
+                    start: mat.start(),
+                    end: mat.end(),
+                },
+                Some(Chain::Solana),
+                TEXT_ADDRESS_SCORE,
+            ));
+        }
+
+        candidates
+    }
+}
+
+impl Default for AddressExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Addresses in hyperlinks to known token sites, for calls that only link a chart
+pub struct LinkExtractor {
+    solana_regex: Regex,
+    evm_regex: Regex,
+    chain_detector: ChainDetector,
+}
+
+impl LinkExtractor {
+    pub fn new() -> Self {
+        Self {
+            solana_regex: Regex::new(SOLANA_ADDRESS_PATTERN).unwrap(),
+            evm_regex: Regex::new(EVM_ADDRESS_PATTERN).unwrap(),
+            chain_detector: ChainDetector::new(),
+        }
+    }
+}
+
+impl CandidateExtractor for LinkExtractor {
+    fn candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate> {
+        let context_chain = self.chain_detector.detect(&content.text);
+        let mut candidates = Vec::new();
+
+        for url in &content.urls {
+            for link_address in link_addresses(url, &[&self.evm_regex, &self.solana_regex]) {
+                // A chain named by the link beats the one inferred from the text
+                let link_chain = link_address.chain.filter(|chain| *chain != Chain::Solana);
+                let chain = address_chain(&link_address.address, link_chain.or(context_chain));
+
+                candidates.push(ScoredCandidate::address(
+                    &link_address.address,
+                    CandidatePosition::Link { url: url.clone() },
+                    chain,
+                    LINK_ADDRESS_SCORE,
+                ));
+            }
+        }
+
+        candidates
+    }
+}
+
+impl Default for LinkExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Call bots and most callers label the contract, e.g. "CA: <address>" or "Mint: `<address>`"
+pub struct LabeledAddressExtractor {
+    labeled_regex: Regex,
+    chain_detector: ChainDetector,
+}
+
+impl LabeledAddressExtractor {
+    pub fn new() -> Self {
+        let pattern = format!(
+            r"(?i)\b(?:CA|contract(?: address)?|token address|mint)\b\s*[:=\-]?\s*`?({}|{})",
+            EVM_ADDRESS_PATTERN, SOLANA_ADDRESS_PATTERN
+        );
+
+        Self {
+            labeled_regex: Regex::new(&pattern).unwrap(),
+            chain_detector: ChainDetector::new(),
+        }
+    }
+}
+
+impl CandidateExtractor for LabeledAddressExtractor {
+    fn candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate> {
+        let context_chain = self.chain_detector.detect(&content.text);
+
+        self.labeled_regex
+            .captures_iter(&content.text)
+            .filter_map(|captures| captures.get(1))
+            .map(|mat| {
+                ScoredCandidate::address(
+                    mat.as_str(),
+                    CandidatePosition::Text {
+                        start: mat.start(),
+                        end: mat.end(),
+                    },
+                    address_chain(mat.as_str(), context_chain),
+                    LABELED_ADDRESS_SCORE,
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for LabeledAddressExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Layouts of the call bots groups use, where the token address sits on a line of its own:
+/// - tree layouts (Phanes, Maestro): `└ <address>` under the token name
+/// - Rick: the `🌐 <chain> @ <dex>` header, with the address alone on a later line
+pub struct CallBotExtractor {
+    tree_regex: Regex,
+    rick_header_regex: Regex,
+    own_line_regex: Regex,
+    chain_detector: ChainDetector,
+}
+
+impl CallBotExtractor {
+    pub fn new() -> Self {
+        let address = format!("({}|{})", EVM_ADDRESS_PATTERN, SOLANA_ADDRESS_PATTERN);
+
+        Self {
+            tree_regex: Regex::new(&format!(r"(?m)^[ \t]*[├└][─ \t]*`?{}`?[ \t]*$", address))
+                .unwrap(),
+            rick_header_regex: Regex::new(r"(?m)^🌐 (\w+) @ ").unwrap(),
+            own_line_regex: Regex::new(&format!(r"(?m)^[ \t]*`?{}`?[ \t]*$", address)).unwrap(),
+            chain_detector: ChainDetector::new(),
+        }
+    }
+}
+
+impl CandidateExtractor for CallBotExtractor {
+    fn candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate> {
+        let text = content.text.as_str();
+        let mut context_chain = self.chain_detector.detect(text);
+
+        // 1. Tree layouts
+        let mut spans: Vec<(usize, usize)> = self
+            .tree_regex
+            .captures_iter(text)
+            .filter_map(|captures| captures.get(1))
+            .map(|mat| (mat.start(), mat.end()))
+            .collect();
+
+        // 2. Rick names the chain in its header, an EVM one beats the one inferred from the text
+        if let Some(header) = self.rick_header_regex.captures(text) {
+            if let Some(chain) =
+                Chain::from_id(&header[1]).filter(|chain| EVM_CHAINS.contains(chain))
+            {
+                context_chain = Some(chain);
+            }
+            let header_end = header.get(0).unwrap().end();
+            spans.extend(
+                self.own_line_regex
+                    .captures_iter(&text[header_end..])
+                    .filter_map(|captures| captures.get(1))
+                    .map(|mat| (header_end + mat.start(), header_end + mat.end())),
+            );
+        }
+
+        spans
+            .into_iter()
+            .map(|(start, end)| {
+                let address = &text[start..end];
+                ScoredCandidate::address(
+                    address,
+                    CandidatePosition::Text { start, end },
+                    address_chain(address, context_chain),
+                    CALL_BOT_ADDRESS_SCORE,
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for CallBotExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CandidateExtractor for TickerExtractor {
+    fn candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate> {
+        self.extract(&content.text)
+            .into_iter()
+            .map(|mention| ScoredCandidate {
+                position: CandidatePosition::Ticker {
+                    start: mention.start,
+                    end: mention.end,
+                },
+                chain: Some(Chain::Solana),
+                score: mention.kind.weight(),
+                value: CandidateValue::Ticker(mention),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLANA_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+    /// No zero after the prefix, so the whole address is valid base58 too
+    const EVM_TOKEN: &str = "0xa1b2c3d4e5f6a7b8c9d1e2f3a4b5c6d7e8f9a1b2";
+
+    fn content(text: &str, urls: &[&str]) -> MessageContent {
+        MessageContent {
+            chat_id: 1,
+            text: text.to_string(),
+            urls: urls.iter().map(|url| url.to_string()).collect(),
+        }
+    }
+
+    fn addresses(candidates: &[ScoredCandidate]) -> Vec<&str> {
+        candidates
+            .iter()
+            .filter_map(|candidate| match &candidate.value {
+                CandidateValue::Address(address) => Some(address.as_str()),
+                CandidateValue::Ticker(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn address_extractor_skips_base58_matches_inside_0x_addresses() {
+        let text = format!("{} and {}", EVM_TOKEN, SOLANA_MINT);
+        let candidates = AddressExtractor::new().candidates(&content(&text, &[]));
+
+        assert_eq!(addresses(&candidates), vec![EVM_TOKEN, SOLANA_MINT]);
+        assert_eq!(candidates[0].chain, None);
+        assert_eq!(candidates[1].chain, Some(Chain::Solana));
+        assert_eq!(
+            candidates[1].position,
+            CandidatePosition::Text {
+                start: EVM_TOKEN.len() + 5,
+                end: text.len(),
+            }
+        );
+    }
+
+    #[test]
+    fn labeled_address_extractor_matches_labels_only() {
+        let extractor = LabeledAddressExtractor::new();
+
+        for text in [
+            format!("CA: {}", SOLANA_MINT),
+            format!("contract address = `{}`", SOLANA_MINT),
+            format!("Mint - {}", SOLANA_MINT),
+        ] {
+            let candidates = extractor.candidates(&content(&text, &[]));
+            assert_eq!(addresses(&candidates), vec![SOLANA_MINT], "{}", text);
+            assert_eq!(candidates[0].score, LABELED_ADDRESS_SCORE);
+        }
+
+        let unlabeled = format!("new gem {}", SOLANA_MINT);
+        assert!(extractor.candidates(&content(&unlabeled, &[])).is_empty());
+    }
+
+    #[test]
+    fn link_extractor_prefers_the_chain_named_by_the_link() {
+        let extractor = LinkExtractor::new();
+
+        let dexscreener = format!("https://dexscreener.com/base/{}", EVM_TOKEN);
+        let candidates = extractor.candidates(&content("#eth", &[&dexscreener]));
+        assert_eq!(addresses(&candidates), vec![EVM_TOKEN]);
+        assert_eq!(candidates[0].chain, Some(Chain::Base));
+
+        let etherscan = format!("https://etherscan.io/token/{}", EVM_TOKEN);
+        let candidates = extractor.candidates(&content("on base", &[&etherscan]));
+        assert_eq!(candidates[0].chain, Some(Chain::Ethereum));
+
+        // Without a chain in the link, the text decides
+        let bullx = format!("https://bullx.io/terminal?address={}", EVM_TOKEN);
+        let candidates = extractor.candidates(&content("on base", &[&bullx]));
+        assert_eq!(candidates[0].chain, Some(Chain::Base));
+    }
+
+    #[test]
+    fn link_extractor_ignores_wallet_pages() {
+        let wallet = format!("https://solscan.io/account/{}", SOLANA_MINT);
+        let candidates = LinkExtractor::new().candidates(&content("", &[&wallet]));
+
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn call_bot_extractor_reads_tree_and_rick_layouts() {
+        let extractor = CallBotExtractor::new();
+
+        let tree = format!("Bonk (BONK)\n└ {}\n└ MC: $1.2B", SOLANA_MINT);
+        let candidates = extractor.candidates(&content(&tree, &[]));
+        assert_eq!(addresses(&candidates), vec![SOLANA_MINT]);
+        assert_eq!(candidates[0].chain, Some(Chain::Solana));
+
+        let rick = format!(
+            "🟢 PEPE [1.2M/5%] $PEPE\n🌐 Base @ Uniswap\n💰 USD: $0.01\n\n{}",
+            EVM_TOKEN
+        );
+        let candidates = extractor.candidates(&content(&rick, &[]));
+        assert_eq!(addresses(&candidates), vec![EVM_TOKEN]);
+        assert_eq!(candidates[0].chain, Some(Chain::Base));
+        assert_eq!(candidates[0].score, CALL_BOT_ADDRESS_SCORE);
+
+        let plain = format!("aping\n{}", SOLANA_MINT);
+        assert!(extractor.candidates(&content(&plain, &[])).is_empty());
+    }
+
+    #[test]
+    fn call_bot_extractor_never_puts_0x_addresses_on_solana() {
+        let extractor = CallBotExtractor::new();
+
+        let rick = format!(
+            "🟢 PEPE [1.2M/5%] $PEPE\n🌐 Solana @ Raydium\n\n{}\n\n{}",
+            EVM_TOKEN, SOLANA_MINT
+        );
+        let candidates = extractor.candidates(&content(&rick, &[]));
+        assert_eq!(addresses(&candidates), vec![EVM_TOKEN, SOLANA_MINT]);
+        assert_eq!(candidates[0].chain, None);
+        assert_eq!(candidates[1].chain, Some(Chain::Solana));
+    }
+
+    #[test]
+    fn run_extractors_keeps_the_first_position_and_the_best_score() {
+        let text = format!("CA: {}", SOLANA_MINT);
+        let url = format!("https://dexscreener.com/solana/{}", SOLANA_MINT);
+        let candidates = run_extractors(&default_extractors(), &content(&text, &[&url]));
+
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(
+            candidates[0].position,
+            CandidatePosition::Text {
+                start: 4,
+                end: text.len(),
+            }
+        );
+        assert_eq!(candidates[0].score, LABELED_ADDRESS_SCORE);
+    }
+
+    #[test]
+    fn run_extractors_skips_extractors_for_other_chats() {
+        struct OtherChat;
+
+        impl CandidateExtractor for OtherChat {
+            fn applies_to(&self, chat_id: i64) -> bool {
+                chat_id == 2
+            }
+
+            fn candidates(&self, _content: &MessageContent) -> Vec<ScoredCandidate> {
+                vec![ScoredCandidate::address(
+                    SOLANA_MINT,
+                    CandidatePosition::Text { start: 0, end: 0 },
+                    Some(Chain::Solana),
+                    1.0,
+                )]
+            }
+        }
+
+        let extractors: Vec<Box<dyn CandidateExtractor>> = vec![Box::new(OtherChat)];
+        assert!(run_extractors(&extractors, &content("", &[])).is_empty());
+    }
+}
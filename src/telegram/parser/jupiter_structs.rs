@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Response from: https://lite-api.jup.ag/price/v3?ids=:mints
+/// Mints Jupiter can't price are left out of the map
+pub type JupiterPriceResponse = HashMap<String, JupiterPrice>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JupiterPrice {
+    pub usd_price: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    #[serde(rename = "priceChange24h", skip_serializing_if = "Option::is_none")]
+    pub price_change_24h: Option<f64>,
+}
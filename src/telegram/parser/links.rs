@@ -0,0 +1,218 @@
+use super::Chain;
+use grammers_client::types::Message;
+use grammers_tl_types as tl;
+use regex::Regex;
+use reqwest::Url;
+
+/// Where a site puts the token or pair address in its links
+struct TokenSite {
+    domain: &'static str,
+    /// Paths followed by the address segment, `*` matches any one segment
+    /// and an empty prefix means the first segment
+    path_prefixes: &'static [&'static str],
+    /// Query parameters holding the address
+    query_params: &'static [&'static str],
+}
+
+/// Sites whose links carry a token or pair address, e.g. dexscreener.com/solana/<pair>
+/// or bullx.io/terminal?address=<mint>. Wallet and transaction pages are left out.
+const TOKEN_SITES: [TokenSite; 16] = [
+    TokenSite {
+        domain: "dexscreener.com",
+        path_prefixes: &["*"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "birdeye.so",
+        path_prefixes: &["token", "*/token"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "pump.fun",
+        path_prefixes: &["", "coin"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "solscan.io",
+        path_prefixes: &["token"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "raydium.io",
+        path_prefixes: &[],
+        query_params: &["inputMint", "outputMint"],
+    },
+    // jup.ag/swap/SOL-<mint>
+    TokenSite {
+        domain: "jup.ag",
+        path_prefixes: &["swap", "tokens"],
+        query_params: &[],
+    },
+    // photon-sol.tinyastro.io/en/lp/<pair>
+    TokenSite {
+        domain: "tinyastro.io",
+        path_prefixes: &["lp", "*/lp"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "bullx.io",
+        path_prefixes: &[],
+        query_params: &["address"],
+    },
+    TokenSite {
+        domain: "dextools.io",
+        path_prefixes: &["app/*/*/pair-explorer"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "geckoterminal.com",
+        path_prefixes: &["*/pools", "*/tokens"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "etherscan.io",
+        path_prefixes: &["token"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "basescan.org",
+        path_prefixes: &["token"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "bscscan.com",
+        path_prefixes: &["token"],
+        query_params: &[],
+    },
+    TokenSite {
+        domain: "uniswap.org",
+        path_prefixes: &["explore/tokens/*", "explore/pools/*"],
+        query_params: &["inputCurrency", "outputCurrency"],
+    },
+    TokenSite {
+        domain: "pancakeswap.finance",
+        path_prefixes: &["info/tokens", "info/*/tokens"],
+        query_params: &["inputCurrency", "outputCurrency"],
+    },
+    TokenSite {
+        domain: "defined.fi",
+        path_prefixes: &["*"],
+        query_params: &[],
+    },
+];
+
+impl TokenSite {
+    /// The parts of the link that may hold the address
+    fn address_parts(&self, url: &Url) -> Vec<String> {
+        let segments: Vec<&str> = url
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        let mut parts: Vec<String> = self
+            .path_prefixes
+            .iter()
+            .filter_map(|prefix| {
+                let pattern: Vec<&str> = prefix.split('/').filter(|p| !p.is_empty()).collect();
+                let matches = segments.len() > pattern.len()
+                    && pattern
+                        .iter()
+                        .zip(&segments)
+                        .all(|(p, segment)| *p == "*" || p.eq_ignore_ascii_case(segment));
+                matches.then(|| segments[pattern.len()].to_string())
+            })
+            .collect();
+        parts.extend(
+            url.query_pairs()
+                .filter(|(key, _)| self.query_params.contains(&key.as_ref()))
+                .map(|(_, value)| value.into_owned()),
+        );
+
+        parts
+    }
+}
+
+/// An address found in a link, with the chain the link points to if it names one
+pub struct LinkAddress {
+    pub address: String,
+    pub chain: Option<Chain>,
+}
+
+/// Every URL in the message: hidden hyperlinks (`MessageEntityTextUrl`) and
+/// plain links in the text (`MessageEntityUrl`)
+pub fn message_urls(message: &Message) -> Vec<String> {
+    let entities = match message.fmt_entities() {
+        Some(entities) => entities,
+        None => return Vec::new(),
+    };
+
+    // Entity offsets and lengths are counted in UTF-16 code units
+    let utf16_text: Vec<u16> = message.text().encode_utf16().collect();
+
+    entities
+        .iter()
+        .filter_map(|entity| match entity {
+            tl::enums::MessageEntity::TextUrl(entity) => Some(entity.url.clone()),
+            tl::enums::MessageEntity::Url(entity) => {
+                let start = entity.offset as usize;
+                let end = start + entity.length as usize;
+                utf16_text.get(start..end).map(String::from_utf16_lossy)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Addresses matching any of `address_regexes` where a known token site puts
+/// the token or pair address of its links. Pass the 0x regex before the base58 one.
+pub fn link_addresses(url: &str, address_regexes: &[&Regex]) -> Vec<LinkAddress> {
+    let url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+
+    let url = match Url::parse(&url) {
+        Ok(url) => url,
+        Err(_) => return Vec::new(),
+    };
+
+    let site = url.host_str().and_then(|host| {
+        TOKEN_SITES
+            .iter()
+            .find(|site| host == site.domain || host.ends_with(&format!(".{}", site.domain)))
+    });
+    let site = match site {
+        Some(site) => site,
+        None => return Vec::new(),
+    };
+
+    let chain = Chain::from_url(&url);
+    let mut addresses: Vec<LinkAddress> = Vec::new();
+    for part in site.address_parts(&url) {
+        // Earlier regexes win, so base58 matches inside 0x addresses are skipped
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for regex in address_regexes {
+            for mat in regex.find_iter(&part) {
+                if spans
+                    .iter()
+                    .any(|(start, end)| mat.start() < *end && *start < mat.end())
+                {
+                    continue;
+                }
+                spans.push((mat.start(), mat.end()));
+
+                if !addresses.iter().any(|link| link.address == mat.as_str()) {
+                    addresses.push(LinkAddress {
+                        address: mat.as_str().to_string(),
+                        chain,
+                    });
+                }
+            }
+        }
+    }
+
+    addresses
+}
@@ -0,0 +1,230 @@
+use super::{read_pubkey, Extensions};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+pub const METADATA_PROGRAM: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bT518x1s";
+
+/// First byte of a `Metadata` account, `Key::MetadataV1`
+const METADATA_V1_KEY: u8 = 4;
+
+/// Socials copied from the off-chain JSON, named like Birdeye's `extensions`
+const SOCIAL_KEYS: [&str; 5] = ["website", "twitter", "telegram", "discord", "description"];
+
+/// Metaplex token metadata, the name and symbol wallets and explorers show
+#[derive(Debug, Clone, Serialize)]
+pub struct Metadata {
+    pub update_authority: String,
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    /// Link to the off-chain JSON with the image and socials
+    pub uri: String,
+}
+
+impl Metadata {
+    /// Decodes the account data, None if it isn't a metadata account
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if *data.first()? != METADATA_V1_KEY {
+            return None;
+        }
+
+        let (name, offset) = read_string(data, 65)?;
+        let (symbol, offset) = read_string(data, offset)?;
+        let (uri, _) = read_string(data, offset)?;
+
+        Some(Self {
+            update_authority: read_pubkey(data, 1)?.to_string(),
+            mint: read_pubkey(data, 33)?.to_string(),
+            name,
+            symbol,
+            uri,
+        })
+    }
+
+    /// Every mint has its metadata at the PDA ["metadata", program, mint]
+    pub fn address_for_mint(mint: &Pubkey) -> Pubkey {
+        let program = Pubkey::from_str(METADATA_PROGRAM).unwrap();
+        Pubkey::find_program_address(&[b"metadata", program.as_ref(), mint.as_ref()], &program).0
+    }
+}
+
+/// The JSON behind a metadata uri. There is no enforced schema, launchpads put socials
+/// either at the top level or under `extensions`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OffChainMetadata {
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(flatten)]
+    pub fields: HashMap<String, serde_json::Value>,
+}
+
+impl OffChainMetadata {
+    /// The URL to fetch a metadata uri from. The uri is chosen by the token creator,
+    /// so only public http(s) URLs are accepted, ipfs:// and ar:// go through public gateways
+    pub fn url(uri: &str) -> Option<Url> {
+        let uri = uri.trim();
+        let uri = if let Some(path) = uri.strip_prefix("ipfs://") {
+            format!("https://ipfs.io/ipfs/{}", path.trim_start_matches("ipfs/"))
+        } else if let Some(path) = uri.strip_prefix("ar://") {
+            format!("https://arweave.net/{}", path)
+        } else {
+            uri.to_string()
+        };
+
+        let url = Url::parse(&uri).ok()?;
+        Self::is_public_url(&url).then_some(url)
+    }
+
+    /// http(s) URLs whose host isn't localhost or a non-public IP.
+    /// Hostnames can still resolve to one, `PublicResolver` checks what they resolve to.
+    pub fn is_public_url(url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+
+        match url.host_str() {
+            None => false,
+            Some(host)
+                if host.eq_ignore_ascii_case("localhost") || host.ends_with(".localhost") =>
+            {
+                false
+            }
+            Some(host) => match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+                Ok(ip) => is_public_ip(ip),
+                Err(_) => true,
+            },
+        }
+    }
+
+    /// Socials found in the JSON, None if there are none
+    pub fn socials(&self) -> Option<Extensions> {
+        let nested = self
+            .fields
+            .get("extensions")
+            .and_then(|value| value.as_object());
+        let properties: HashMap<String, Option<String>> = SOCIAL_KEYS
+            .iter()
+            .filter_map(|key| {
+                let value = self
+                    .fields
+                    .get(*key)
+                    .or_else(|| nested.and_then(|nested| nested.get(*key)))?
+                    .as_str()?
+                    .trim();
+                (!value.is_empty()).then(|| (key.to_string(), Some(value.to_string())))
+            })
+            .collect();
+
+        (!properties.is_empty()).then_some(Extensions { properties })
+    }
+}
+
+/// Borsh string: u32 length then the bytes, which Metaplex pads with NULs
+fn read_string(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+    let start = offset + 4;
+    let bytes = data.get(start..start + len)?;
+    let value = String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string();
+
+    Some((value, start + len))
+}
+
+/// Resolves hostnames to their public IPs only, so a creator-chosen metadata uri
+/// can't reach the internal network through a hostname pointing at it
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public IP", name.as_str()).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// False for loopback, private, link-local, shared (100.64.0.0/10) and other
+/// non-routable IPs, IPv4-mapped IPv6 addresses included
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => {
+                let segment = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || (segment & 0xfe00) == 0xfc00 // unique local
+                    || (segment & 0xffc0) == 0xfe80) // link-local
+            }
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || first == 0 // "this network"
+        || (first == 100 && (second & 0xc0) == 64)) // shared address space, 100.64.0.0/10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_public(url: &str) -> bool {
+        OffChainMetadata::is_public_url(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn accepts_public_hosts() {
+        assert!(is_public("https://arweave.net/abc"));
+        assert!(is_public("http://1.1.1.1/metadata.json"));
+        assert!(is_public("https://[2606:4700::1111]/"));
+        assert_eq!(
+            OffChainMetadata::url("ipfs://ipfs/Qm123").map(String::from),
+            Some(String::from("https://ipfs.io/ipfs/Qm123"))
+        );
+    }
+
+    #[test]
+    fn rejects_internal_hosts() {
+        assert!(!is_public("file:///etc/passwd"));
+        assert!(!is_public("http://localhost:8080/"));
+        assert!(!is_public("http://127.0.0.1/"));
+        assert!(!is_public("http://2130706433/"));
+        assert!(!is_public("http://10.0.0.1/"));
+        assert!(!is_public("http://169.254.169.254/latest/meta-data"));
+        assert!(!is_public("http://100.100.100.200/"));
+        assert!(!is_public("http://[::1]/"));
+        assert!(!is_public("http://[::ffff:127.0.0.1]/"));
+        assert!(!is_public("http://[::ffff:a9fe:a9fe]/"));
+        assert!(!is_public("http://[fd00::1]/"));
+    }
+
+    #[tokio::test]
+    async fn resolver_drops_non_public_ips() {
+        let name = Name::from_str("localhost").unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+    }
+}
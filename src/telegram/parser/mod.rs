@@ -1,7 +1,32 @@
 mod birdeye_structs;
+mod chains;
+mod denylist;
 mod dexscreener_structs;
+mod extractors;
+mod jupiter_structs;
+mod links;
+mod metaplex;
+#[allow(clippy::module_inception)]
 mod parser;
+mod pools;
+mod providers;
+mod pump_fun;
+mod resolver;
+mod tickers;
+mod token_2022;
 
 pub use birdeye_structs::*;
+pub use chains::*;
+pub use denylist::*;
 pub use dexscreener_structs::*;
+pub use extractors::*;
+pub use jupiter_structs::*;
+pub use links::*;
+pub use metaplex::*;
 pub use parser::*;
+pub use pools::*;
+pub use providers::*;
+pub use pump_fun::*;
+pub use resolver::*;
+pub use tickers::*;
+pub use token_2022::*;
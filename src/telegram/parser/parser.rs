@@ -1,89 +1,293 @@
-use super::birdeye_structs::{SolanaTokenOverview, SolanaTokenOverviewResponse};
-use super::{DexscreenerPairsResponse, Extensions, SolanaTokenCall, SolanaTokenOverviewData};
-use crate::telegram::TelegramAccount;
+use super::{
+    default_extractors, run_extractors, BondingCurve, CandidateExtractor, CandidateValue, Chain,
+    Extensions, MarketDataProvider, MarketSnapshot, MessageContent, MintExtensions, Pool, RiskFlag,
+    ScoredCandidate, SkipReason, SolanaTokenCall, TickerResolution, TokenResolver,
+};
+use crate::telegram::{positive_env_or, BoundedCache, TelegramAccount, TrackedGroups};
+use chrono::DateTime;
 use grammers_client::types::{Chat, Message};
-use regex::Regex;
-use solana_account_decoder::parse_token::parse_token;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
-use std::env;
-use std::str::FromStr;
-
-#[derive(Debug)]
-pub struct ParseResult<'a> {
-    pub token: Token,
+use grammers_client::Client;
+use grammers_tl_types as tl;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseResult {
+    /// Every address candidate found in the message, accepted or not, in message order
+    pub token_calls: Vec<TokenCall>,
     pub call: Call,
-    pub sender: TelegramSender<'a>,
+    pub sender: TelegramSender,
 }
 
-#[derive(Debug)]
+impl ParseResult {
+    pub fn accepted_addresses(&self) -> Vec<String> {
+        self.token_calls
+            .iter()
+            .filter(|token_call| token_call.accepted)
+            .map(|token_call| token_call.address.clone())
+            .collect()
+    }
+}
+
+/// One address candidate of a message and what came out of it
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenCall {
+    pub address: String,
+    pub position: CandidatePosition,
+    pub accepted: bool,
+    pub status: CandidateStatus,
+    /// Error behind a rejection, if any
+    pub detail: Option<String>,
+    /// Set when the address is denylisted and was never looked up
+    pub skip_reason: Option<SkipReason>,
+    /// Chain the candidate was looked up on, None when no chain had it
+    pub chain: Option<Chain>,
+    /// Score of the extractor that found the address, or how ambiguous the ticker was
+    /// for tokens resolved from a ticker mention
+    pub confidence: f64,
+    /// Set when the address was resolved from a ticker instead of written in the message
+    pub ticker: Option<TickerResolution>,
+    /// Pump.fun curve of the token at call time, for tokens launched there
+    pub bonding_curve: Option<BondingCurve>,
+    /// The pool the candidate address pointed to, decoded from chain data
+    pub pool: Option<Pool>,
+    pub token: Option<Token>,
+    /// Market data at call time, from the first provider that had the token
+    pub market: Option<MarketSnapshot>,
+    /// Birdeye's detailed breakdown, only set when Birdeye supplied the market data
+    pub token_call_data: Option<SolanaTokenCall>,
+}
+
+/// Where in the message a candidate address was found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum CandidatePosition {
+    /// Byte offsets into the message text
+    Text { start: usize, end: usize },
+    /// Inside a hyperlink to a known token site
+    Link { url: String },
+    /// Resolved from a ticker mention at these byte offsets of the message text
+    Ticker { start: usize, end: usize },
+}
+
+/// Why a candidate was accepted or rejected
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateStatus {
+    /// Accepted: the address is a token mint
+    TokenMint,
+    /// Accepted: the address is a Raydium AMM v4 pair, the call is for its base token
+    RaydiumPair,
+    /// Accepted: the address is a pump.fun bonding curve, the call is for its mint
+    PumpFunCurve,
+    /// Accepted: the address is a Raydium CPMM/CLMM, Orca or Meteora pool,
+    /// the call is for its base mint
+    Pool,
+    /// Accepted: the address is an EVM token contract
+    TokenContract,
+    /// Accepted: the address is an EVM pair, the call is for its base token
+    EvmPair,
+    /// Not a valid Solana public key
+    InvalidAddress,
+    /// The account could not be fetched, it may not exist
+    AccountUnavailable,
+    /// An account that is neither a mint nor a pair, e.g. a wallet
+    NotATokenOrPair,
+    /// A token or pair without market data
+    NoMarketData,
+    /// The market data request failed
+    MarketDataError,
+    /// Accepted: a ticker mention resolved to a token with enough confidence
+    TickerMatch,
+    /// A ticker mention whose best match is below the confidence threshold
+    LowConfidenceTicker,
+    /// A denylisted address, see `skip_reason`
+    Skipped,
+}
+
+impl CandidateStatus {
+    pub fn is_accepted(&self) -> bool {
+        matches!(
+            self,
+            Self::TokenMint
+                | Self::RaydiumPair
+                | Self::PumpFunCurve
+                | Self::Pool
+                | Self::TokenContract
+                | Self::EvmPair
+                | Self::TickerMatch
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Token {
     pub address: String,
-    pub decimals: u8,
+    /// None for EVM tokens priced by a provider that doesn't report decimals
+    pub decimals: Option<u8>,
     pub name: String,
     pub symbol: String,
     pub network: String,
     pub logo_uri: Option<String>,
     pub extensions: Option<Extensions>,
+    /// Token-2022 extensions of the mint, None for classic SPL and EVM tokens
+    pub token_2022: Option<MintExtensions>,
+    pub risk_flags: Vec<RiskFlag>,
+    /// Metaplex update authority, only read when the token came from chain data
+    pub update_authority: Option<String>,
+    /// Built from on-chain metadata because no provider has market data yet, there are
+    /// no prices in the call
+    pub market_data_pending: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Call {
     pub timestamp: String,
     pub is_channel_call: bool,
     pub message_text: String,
-    pub token_call_data: SolanaTokenCall,
+    pub chat_id: i64,
+    pub message_id: i32,
+    pub event: CallEvent,
+    /// 0 for the original call, incremented every time an edit changes the token addresses
+    pub revision: u32,
+    /// Accepted token addresses before the edit that produced this revision
+    pub previous_addresses: Vec<String>,
+    pub edited_at: Option<String>,
+    /// Deleted calls are kept so bad calls still count in caller statistics
+    pub deleted: bool,
+    /// Forum topic the call was posted in, None outside of forums and in the General topic
+    pub topic: Option<ForumTopic>,
+    pub reply_to: Option<ReplyContext>,
+    /// Where the call was forwarded from. The sender is still whoever forwarded it,
+    /// leaderboards should credit this origin instead.
+    pub forwarded_from: Option<ForwardOrigin>,
+    /// Providers that supplied the market data of the accepted tokens
+    pub market_data_providers: Vec<MarketDataProvider>,
 }
 
-#[derive(Debug)]
-pub struct TelegramSender<'a> {
-    pub telegram_id: i64, // as per gramme.rs library
-    pub first_name: Option<&'a str>,
-    pub last_name: Option<&'a str>,
-    pub username: Option<&'a str>, // for channels this is the title
-    pub is_channel: bool,
+#[derive(Debug, Clone, Serialize)]
+pub struct ForumTopic {
+    pub id: i32,
+    pub title: Option<String>,
 }
 
-pub struct DefaultParser {
-    telegram_account: TelegramAccount,
-    solana_regex: Regex,
-    birdeye_api_key: String,
-    reqwest_client: reqwest::Client,
-    solana_client: RpcClient,
+/// The message a call replies to
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplyContext {
+    pub message_id: i32,
+    /// Top message of the comment thread, for replies in channel discussion threads
+    pub thread_id: Option<i32>,
+    /// Part of the replied message the caller quoted
+    pub quote_text: Option<String>,
 }
 
-enum SolanaAccountType {
-    Token,
-    RaydiumPair,
-    Other,
+/// The forward header of a forwarded call
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardOrigin {
+    pub channel_id: Option<i64>,
+    pub user_id: Option<i64>,
+    /// Set instead of an id when the original sender hides their account
+    pub name: Option<String>,
+    /// Id of the original post, only known for channel posts
+    pub message_id: Option<i32>,
+    pub post_author: Option<String>,
+    pub timestamp: String,
+    /// Seconds between the original post and the forward, to measure how fast calls spread
+    pub spread_secs: i64,
+    /// The origin channel is tracked, so the original call is recorded under
+    /// (channel_id, message_id) as well
+    pub is_tracked_source: bool,
+    /// The original call was seen by this process
+    pub original_recorded: bool,
 }
 
-impl DefaultParser {
-    pub fn new(telegram_account: TelegramAccount) -> Self {
-        let solana_regex = Regex::new(r"[1-9A-HJ-NP-Za-km-z]{32,44}").unwrap();
-        let birdeye_api_key = env::var("BIRDEYE_API_KEY").unwrap();
-        let reqwest_client = reqwest::Client::new();
-        let solana_client = RpcClient::new(env::var("SOLANA_RPC_URL").unwrap());
+/// What happened to the message a call was parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallEvent {
+    Created,
+    /// The message was edited to call a different token
+    Revised,
+    Deleted,
+}
 
-        Self {
-            telegram_account,
-            solana_regex,
-            birdeye_api_key,
-            reqwest_client,
-            solana_client,
-        }
+#[derive(Debug, Clone, Serialize)]
+pub struct TelegramSender {
+    pub telegram_id: i64, // as per gramme.rs library
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub username: Option<String>, // for channels this is the title
+    pub is_channel: bool,
+    pub kind: SenderKind,
+    /// The sender was missing from the update's chat cache and had to be fetched
+    pub fetched: bool,
+}
+
+/// Which case of sender resolution produced a `TelegramSender`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SenderKind {
+    User,
+    /// Broadcast channel posting as itself
+    ChannelPost,
+    /// Anonymous group admin, the sender is the group itself
+    AnonymousAdmin,
+    /// Group member posting as one of their channels ("send as")
+    SendAsChannel,
+}
+
+/// Turns Telegram messages into calls. The pipeline runs any implementation,
+/// `DefaultParser` is the one the listener and the backfill use.
+pub trait MessageParser: Send + Sync {
+    /// `client` must belong to the account that received the message,
+    /// as it is used to fetch senders missing from the update
+    fn parse(
+        &self,
+        client: &Client,
+        message: Message,
+    ) -> impl Future<Output = Result<Option<ParseResult>, Box<dyn std::error::Error>>> + Send;
+
+    /// Lets the parser fetch what a batch of messages needs at once, before they are parsed
+    fn prefetch(&self, _messages: &[Message]) -> impl Future<Output = ()> + Send {
+        async {}
     }
+}
 
-    pub async fn parse(
+/// Extractors find scored candidates in the message, the resolver looks them up
+pub struct DefaultParser {
+    tracked_groups: Vec<TrackedGroups>,
+    extractors: Vec<Box<dyn CandidateExtractor>>,
+    resolver: TokenResolver,
+    /// Titles of the most recent forum topics by (chat_id, topic_id), so each topic
+    /// is only fetched once until it is renamed
+    topic_titles: Mutex<BoundedCache<(i64, i32), String>>,
+}
+
+impl MessageParser for DefaultParser {
+    async fn parse(
         &self,
+        client: &Client,
         message: Message,
     ) -> Result<Option<ParseResult>, Box<dyn std::error::Error>> {
-        // 1. Ignore messages in chats we are not tracking
-        if !self
-            .telegram_account
-            .tracked_groups
-            .contains_key(&message.chat().id())
-        {
+        // 1. Ignore messages in chats and forum topics we are not tracking
+        let chat_id = message.chat().id();
+        let (topic_id, reply_to) = Self::extract_thread(&message);
+        // Renamed topics have their cached title dropped
+        if let Some(tl::enums::MessageAction::TopicEdit(_)) = message.action() {
+            if let Some(topic_id) = topic_id {
+                self.topic_titles
+                    .lock()
+                    .unwrap()
+                    .remove(&(chat_id, topic_id));
+            }
+            return Ok(None);
+        }
+        if !self.tracked_groups.iter().any(|groups| {
+            groups
+                .get(chat_id)
+                .is_some_and(|group| group.topics.allows(topic_id))
+        }) {
             return Ok(None);
         }
 
@@ -92,21 +296,15 @@ impl DefaultParser {
             return Ok(None);
         }
 
-        // 3. Extract token data from message. If no token exit function
-        let (token, call) = match self.extract_token_data(&message).await {
-            Ok(Some(token)) => self.format_token_data(token),
-            Err(err) => {
-                eprint!("Error while parsing: {}", err);
-                return Ok(None);
-            }
-            _ => {
-                println!("Could not find token in message");
-                return Ok(None);
-            }
-        };
+        // 3. Extract token calls from message. If no token was accepted exit function
+        let token_calls = self.extract_token_calls(&message).await;
+        if !token_calls.iter().any(|token_call| token_call.accepted) {
+            println!("Could not find token in message");
+            return Ok(None);
+        }
 
         // 4. Extract Telegram User that made the call. If no sender exit function
-        let sender = match self.extract_sender(&message).await {
+        let sender = match self.extract_sender(client, &message).await {
             Some(sender) => sender,
             _ => {
                 eprintln!("Could not fetch the Sender for the message...");
@@ -115,15 +313,33 @@ impl DefaultParser {
         };
 
         // 5. From Call
+        let topic = match topic_id {
+            Some(id) => Some(ForumTopic {
+                id,
+                title: self.topic_title(client, &message, id).await,
+            }),
+            None => None,
+        };
+
         let call = Call {
             timestamp: message.date().to_string(),
             is_channel_call: sender.is_channel,
-            token_call_data: call,
             message_text: message.text().to_string(),
+            chat_id,
+            message_id: message.id(),
+            event: CallEvent::Created,
+            revision: 0,
+            previous_addresses: Vec::new(),
+            edited_at: message.edit_date().map(|date| date.to_string()),
+            deleted: false,
+            topic,
+            reply_to,
+            forwarded_from: self.extract_forward_origin(&message),
+            market_data_providers: Self::market_data_providers(&token_calls),
         };
 
         let parse_result = ParseResult {
-            token,
+            token_calls,
             call,
             sender,
         };
@@ -131,478 +347,340 @@ impl DefaultParser {
         Ok(Some(parse_result))
     }
 
-    async fn extract_sender(&self, message: &Message) -> Option<TelegramSender> {
-        match message.sender() {
-            Some(Chat::User(user)) => TelegramSender {
+    /// Classifies the Solana addresses of the whole batch with as few RPC requests
+    /// as possible, so parsing the messages afterwards is served from the cache
+    async fn prefetch(&self, messages: &[Message]) {
+        let candidates: Vec<ScoredCandidate> = messages
+            .iter()
+            .flat_map(|message| self.extract_candidates(&MessageContent::from_message(message)))
+            .collect();
+
+        self.resolver.prefetch(&candidates).await;
+    }
+}
+
+impl DefaultParser {
+    /// Builds a parser tracking the union of the groups tracked by every account.
+    /// The sets are shared, so reloads done by an account apply to the parser right away.
+    pub fn new(telegram_accounts: &[TelegramAccount]) -> Self {
+        Self::with_extractors(telegram_accounts, default_extractors())
+    }
+
+    /// Same as `new` with a custom extractor chain, e.g. `default_extractors()`
+    /// plus one for a group's own call format
+    pub fn with_extractors(
+        telegram_accounts: &[TelegramAccount],
+        extractors: Vec<Box<dyn CandidateExtractor>>,
+    ) -> Self {
+        let tracked_groups = telegram_accounts
+            .iter()
+            .map(|account| account.tracked_groups.clone())
+            .collect();
+        let topic_title_capacity = positive_env_or("TOPIC_TITLE_CACHE_CAPACITY", 1_000);
+
+        Self {
+            tracked_groups,
+            extractors,
+            resolver: TokenResolver::new(),
+            topic_titles: Mutex::new(BoundedCache::new(topic_title_capacity)),
+        }
+    }
+
+    async fn extract_sender(&self, client: &Client, message: &Message) -> Option<TelegramSender> {
+        let chat = message.chat();
+
+        // 1. No sender at all: channel posts (and anonymous admins on older layers)
+        let sender = match message.sender() {
+            Some(sender) => sender,
+            None => return Some(Self::sender_from_chat(&chat)),
+        };
+
+        // 2. The chat itself as sender: channel posting as itself or anonymous group admin
+        if sender.id() == chat.id() {
+            return Some(Self::sender_from_chat(&chat));
+        }
+
+        // 3. Senders missing from the update's chat cache come back as empty placeholders
+        let is_cached = !sender.name().is_empty();
+
+        match sender {
+            Chat::User(user) if is_cached => Some(TelegramSender {
                 telegram_id: user.id(),
-                first_name: Some(user.first_name()),
-                last_name: user.last_name(),
-                username: user.username(),
+                first_name: Some(user.first_name().to_string()),
+                last_name: user.last_name().map(str::to_string),
+                username: user.username().map(str::to_string),
                 is_channel: false,
-            },
-            Some(Chat::Channel(channel)) => TelegramSender {
-                telegram_id: channel.id(),
-                username: Some(channel.title()),
+                kind: SenderKind::User,
+                fetched: false,
+            }),
+            Chat::User(user) => self.fetch_user_sender(client, message, user.id()).await,
+            // 4. Any other channel posting in the group is a member using "send as channel"
+            sender if is_cached => Some(TelegramSender {
+                telegram_id: sender.id(),
+                username: Some(sender.name().to_string()),
                 first_name: None,
                 last_name: None,
                 is_channel: true,
-            },
-            Some(Chat::Group(group)) => {
-                println!("message.sender() returned group: {:?}", group);
-                return None;
+                kind: SenderKind::SendAsChannel,
+                fetched: false,
+            }),
+            sender => {
+                self.fetch_channel_sender(client, message, sender.id())
+                    .await
             }
-            None => return None,
+        }
+    }
+
+    /// Splits the reply header into the forum topic and the message actually replied to.
+    /// In forums every message "replies" to its topic's first message, which isn't a real reply.
+    fn extract_thread(message: &Message) -> (Option<i32>, Option<ReplyContext>) {
+        let header = match message.reply_header() {
+            Some(tl::enums::MessageReplyHeader::Header(header)) => header,
+            _ => return (None, None),
+        };
+
+        let reply_to_msg_id = match header.reply_to_msg_id {
+            Some(reply_to_msg_id) => reply_to_msg_id,
+            None => return (None, None),
         };
 
-        None
+        if header.forum_topic {
+            let topic_id = header.reply_to_top_id.unwrap_or(reply_to_msg_id);
+            let reply_to = header.reply_to_top_id.map(|_| ReplyContext {
+                message_id: reply_to_msg_id,
+                thread_id: None,
+                quote_text: header.quote_text,
+            });
+            return (Some(topic_id), reply_to);
+        }
+
+        let reply_to = ReplyContext {
+            message_id: reply_to_msg_id,
+            thread_id: header.reply_to_top_id,
+            quote_text: header.quote_text,
+        };
+        (None, Some(reply_to))
     }
 
-    async fn extract_token_data(
+    /// Topic titles aren't part of messages, so they are fetched once per topic
+    async fn topic_title(
         &self,
+        client: &Client,
         message: &Message,
-    ) -> Result<Option<SolanaTokenOverview>, Box<dyn std::error::Error>> {
-        // 1. Try extract token from message text by searching for solana addresses
-        let solana_addresses: Vec<&str> = self
-            .solana_regex
-            .find_iter(message.text())
-            .map(|mat| mat.as_str())
-            .collect();
+        topic_id: i32,
+    ) -> Option<String> {
+        let chat = message.chat();
+        let cached = self
+            .topic_titles
+            .lock()
+            .unwrap()
+            .get(&(chat.id(), topic_id))
+            .cloned();
+        if let Some(title) = cached {
+            return Some(title);
+        }
+
+        let request = tl::functions::channels::GetForumTopicsById {
+            channel: chat.pack().try_to_input_channel()?,
+            topics: vec![topic_id],
+        };
 
-        // 2. For each found solana address try to query birdeye /token_overview
-        if !solana_addresses.is_empty() {
-            if let Some(data) = self.try_get_token_overview(&solana_addresses).await {
-                return Ok(Some(data));
+        let topics = match client.invoke(&request).await {
+            Ok(tl::enums::messages::ForumTopics::Topics(topics)) => topics.topics,
+            Err(e) => {
+                eprintln!(
+                    "Error while fetching forum topic: {} \nError: {}",
+                    topic_id, e
+                );
+                return None;
             }
-        }
+        };
 
-        // 3. Try extracting token from entities like hyperlinks
-        Ok(None)
+        let title = topics.into_iter().find_map(|topic| match topic {
+            tl::enums::ForumTopic::Topic(topic) => Some(topic.title),
+            tl::enums::ForumTopic::Deleted(_) => None,
+        })?;
+
+        self.topic_titles
+            .lock()
+            .unwrap()
+            .insert((chat.id(), topic_id), title.clone());
+        Some(title)
     }
 
-    async fn try_get_token_overview(&self, addresses: &[&str]) -> Option<SolanaTokenOverview> {
-        for address in addresses {
-            // 1. Determine type of solana account for that address. We are only interested in tokens/pairs
-            // the GET /token_overview from birdeye accepts only token addresses and not pairs,
-            // so if it's a pair we need to get the token address from it
-            let token_overview = match self.determine_account_type(address).await {
-                SolanaAccountType::Token => self.fetch_token_overview(address).await,
-                SolanaAccountType::RaydiumPair => {
-                    self.fetch_token_overview_from_pair(address).await
+    /// Each provider once, in the order of the token calls it priced
+    fn market_data_providers(token_calls: &[TokenCall]) -> Vec<MarketDataProvider> {
+        let mut providers = Vec::new();
+        for token_call in token_calls.iter().filter(|token_call| token_call.accepted) {
+            if let Some(market) = &token_call.market {
+                if !providers.contains(&market.provider) {
+                    providers.push(market.provider);
                 }
-                SolanaAccountType::Other => return None,
-            };
-
-            // 2.  Upon successful fetch, assume the first one is the correct token and return it instantly
-            if let Ok(Some(data)) = token_overview {
-                return Some(data);
             }
         }
 
-        // 3. If none of the fetches in the loop were successful, means none of the addresses were tokens/pairs
-        return None;
+        providers
     }
 
-    async fn determine_account_type(&self, address: &str) -> SolanaAccountType {
-        // 1. Form the solana public key for the inputted address
-        let pub_key = match Pubkey::from_str(address) {
-            Ok(value) => value,
-            Err(e) => {
-                eprintln!(
-                    "Error while trying to form public key for solana address: {} \nError: {}",
-                    address, e
-                );
-                return SolanaAccountType::Other;
-            }
-        };
+    fn extract_forward_origin(&self, message: &Message) -> Option<ForwardOrigin> {
+        let tl::enums::MessageFwdHeader::Header(header) = message.forward_header()?;
 
-        // 2. Fetch solana account info associated with inputted address
-        let account = match self.solana_client.get_account(&pub_key) {
-            Ok(account) => account,
-            Err(e) => {
-                eprintln!(
-                    "Error while trying to get_account for solana address: {} \nError: {}",
-                    address, e
-                );
-                return SolanaAccountType::Other;
-            }
+        let (channel_id, user_id) = match header.from_id {
+            Some(tl::enums::Peer::Channel(peer)) => (Some(peer.channel_id), None),
+            Some(tl::enums::Peer::User(peer)) => (None, Some(peer.user_id)),
+            _ => (None, None),
         };
 
-        // 3. Raydium pairs always have the same owner of Raydium Liq Pool V4: 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8
-        const RAYDIUM_LIQUIDITY_POOL_V4: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
-        if account.owner.to_string() == RAYDIUM_LIQUIDITY_POOL_V4 {
-            return SolanaAccountType::RaydiumPair;
-        }
+        let is_tracked_source = channel_id.is_some_and(|channel_id| {
+            self.tracked_groups
+                .iter()
+                .any(|groups| groups.contains(channel_id))
+        });
+
+        let original_date = DateTime::from_timestamp(header.date as i64, 0)?;
+
+        Some(ForwardOrigin {
+            channel_id,
+            user_id,
+            name: header.from_name,
+            message_id: header.channel_post,
+            post_author: header.post_author,
+            timestamp: original_date.to_string(),
+            spread_secs: (message.date() - original_date).num_seconds(),
+            is_tracked_source,
+            original_recorded: false,
+        })
+    }
 
-        // 4. Attempt to fetch token info. If it's not a token it will throw error
-        match parse_token(&account.data, None) {
-            Ok(_) => return SolanaAccountType::Token,
-            _ => return SolanaAccountType::Other,
+    fn sender_from_chat(chat: &Chat) -> TelegramSender {
+        let kind = match chat {
+            Chat::Channel(_) => SenderKind::ChannelPost,
+            _ => SenderKind::AnonymousAdmin,
         };
+
+        TelegramSender {
+            telegram_id: chat.id(),
+            username: Some(chat.name().to_string()),
+            first_name: None,
+            last_name: None,
+            is_channel: true,
+            kind,
+            fetched: false,
+        }
     }
 
-    async fn fetch_token_overview(
+    /// Fetches a user we only know by id, through the message it sent
+    async fn fetch_user_sender(
         &self,
-        token_address: &str,
-    ) -> Result<Option<SolanaTokenOverview>, Box<dyn std::error::Error>> {
-        let birdeye_token_overview_query = format!(
-            "https://public-api.birdeye.so/defi/token_overview?address={}",
-            token_address
-        );
-
-        let token_overview_response: SolanaTokenOverviewResponse = self
-            .reqwest_client
-            .get(&birdeye_token_overview_query)
-            .header("x-chain", "solana")
-            .header("X-API-KEY", &self.birdeye_api_key)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        if !token_overview_response.success {
-            return Ok(None);
-        }
+        client: &Client,
+        message: &Message,
+        user_id: i64,
+    ) -> Option<TelegramSender> {
+        let request = tl::functions::users::GetUsers {
+            id: vec![tl::types::InputUserFromMessage {
+                peer: message.chat().pack().to_input_peer(),
+                msg_id: message.id(),
+                user_id,
+            }
+            .into()],
+        };
 
-        match token_overview_response.data {
-            SolanaTokenOverviewData::Data(data) => Ok(Some(data)),
-            SolanaTokenOverviewData::Empty(_) => Ok(None),
+        let users = match client.invoke(&request).await {
+            Ok(users) => users,
+            Err(e) => {
+                eprintln!(
+                    "Error while fetching sender user: {} \nError: {}",
+                    user_id, e
+                );
+                return None;
+            }
+        };
+
+        match users.into_iter().next() {
+            Some(tl::enums::User::User(user)) => Some(TelegramSender {
+                telegram_id: user.id,
+                first_name: user.first_name,
+                last_name: user.last_name,
+                username: user.username,
+                is_channel: false,
+                kind: SenderKind::User,
+                fetched: true,
+            }),
+            _ => None,
         }
     }
 
-    async fn fetch_token_overview_from_pair(
+    /// Fetches a "send as" channel we only know by id, through the message it sent
+    async fn fetch_channel_sender(
         &self,
-        pair_address: &str,
-    ) -> Result<Option<SolanaTokenOverview>, Box<dyn std::error::Error>> {
-        // Construct the URL to fetch the pair details
-        let dexscreener_pairs_query = format!(
-            "https://api.dexscreener.com/latest/dex/pairs/solana/{}",
-            pair_address
-        );
-
-        // Make the HTTP request to get the pair
-        let dexscreener_pair_response: DexscreenerPairsResponse = self
-            .reqwest_client
-            .get(&dexscreener_pairs_query)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        // Process the response to possibly get the base token's overview
-        match dexscreener_pair_response.pair {
-            Some(pair) => {
-                // If pair exists, fetch the token overview using the base token address
-                Ok(self.fetch_token_overview(&pair.base_token.address).await?)
+        client: &Client,
+        message: &Message,
+        channel_id: i64,
+    ) -> Option<TelegramSender> {
+        let request = tl::functions::channels::GetChannels {
+            id: vec![tl::types::InputChannelFromMessage {
+                peer: message.chat().pack().to_input_peer(),
+                msg_id: message.id(),
+                channel_id,
             }
-            None => {
-                // If no pair is found, return None
-                Ok(None)
+            .into()],
+        };
+
+        let chats = match client.invoke(&request).await {
+            Ok(tl::enums::messages::Chats::Chats(chats)) => chats.chats,
+            Ok(tl::enums::messages::Chats::Slice(chats)) => chats.chats,
+            Err(e) => {
+                eprintln!(
+                    "Error while fetching sender channel: {} \nError: {}",
+                    channel_id, e
+                );
+                return None;
             }
+        };
+
+        match chats.into_iter().next() {
+            Some(tl::enums::Chat::Channel(channel)) => Some(TelegramSender {
+                telegram_id: channel.id,
+                username: Some(channel.title),
+                first_name: None,
+                last_name: None,
+                is_channel: true,
+                kind: SenderKind::SendAsChannel,
+                fetched: true,
+            }),
+            _ => None,
         }
     }
 
-    pub fn format_token_data(&self, data: SolanaTokenOverview) -> (Token, SolanaTokenCall) {
-        let token = Token {
-            address: data.address.clone(),
-            decimals: data.decimals,
-            name: data.name.clone(),
-            symbol: data.symbol.clone(),
-            network: String::from("solana"), // Assuming Solana network
-            logo_uri: data.logo_uri.clone(),
-            extensions: data.extensions,
+    async fn extract_token_calls(&self, message: &Message) -> Vec<TokenCall> {
+        // 1. Run the extractors. Tickers are a fallback for calls like "$BONK sending"
+        // that don't contain any address
+        let content = MessageContent::from_message(message);
+        let (addresses, tickers): (Vec<ScoredCandidate>, Vec<ScoredCandidate>) = self
+            .extract_candidates(&content)
+            .into_iter()
+            .partition(|candidate| matches!(candidate.value, CandidateValue::Address(_)));
+        let candidates = if addresses.is_empty() {
+            tickers
+        } else {
+            addresses
         };
 
-        let solana_call_data = SolanaTokenCall {
-            liquidity: data.liquidity,
-            price: data.price,
-            history_30m_price: data.history_30m_price,
-            price_change_30m_percent: data.price_change_30m_percent,
-            history_1h_price: data.history_1h_price,
-            price_change_1h_percent: data.price_change_1h_percent,
-            history_2h_price: data.history_2h_price,
-            price_change_2h_percent: data.price_change_2h_percent,
-            history_4h_price: data.history_4h_price,
-            price_change_4h_percent: data.price_change_4h_percent,
-            history_6h_price: data.history_6h_price,
-            price_change_6h_percent: data.price_change_6h_percent,
-            history_8h_price: data.history_8h_price,
-            price_change_8h_percent: data.price_change_8h_percent,
-            history_12h_price: data.history_12h_price,
-            price_change_12h_percent: data.price_change_12h_percent,
-            history_24h_price: data.history_24h_price,
-            price_change_24h_percent: data.price_change_24h_percent,
-            unique_wallet_30m: data.unique_wallet_30m,
-            unique_wallet_history_30m: data.unique_wallet_history_30m,
-            unique_wallet_30m_change_percent: data.unique_wallet_30m_change_percent,
-            unique_wallet_1h: data.unique_wallet_1h,
-            unique_wallet_history_1h: data.unique_wallet_history_1h,
-            unique_wallet_1h_change_percent: data.unique_wallet_1h_change_percent,
-            unique_wallet_2h: data.unique_wallet_2h,
-            unique_wallet_history_2h: data.unique_wallet_history_2h,
-            unique_wallet_2h_change_percent: data.unique_wallet_2h_change_percent,
-            unique_wallet_4h: data.unique_wallet_4h,
-            unique_wallet_history_4h: data.unique_wallet_history_4h,
-            unique_wallet_4h_change_percent: data.unique_wallet_4h_change_percent,
-            unique_wallet_6h: data.unique_wallet_6h,
-            unique_wallet_history_6h: data.unique_wallet_history_6h,
-            unique_wallet_6h_change_percent: data.unique_wallet_6h_change_percent,
-            unique_wallet_8h: data.unique_wallet_8h,
-            unique_wallet_history_8h: data.unique_wallet_history_8h,
-            unique_wallet_8h_change_percent: data.unique_wallet_8h_change_percent,
-            unique_wallet_12h: data.unique_wallet_12h,
-            unique_wallet_history_12h: data.unique_wallet_history_12h,
-            unique_wallet_12h_change_percent: data.unique_wallet_12h_change_percent,
-            unique_wallet_24h: data.unique_wallet_24h,
-            unique_wallet_history_24h: data.unique_wallet_history_24h,
-            unique_wallet_24h_change_percent: data.unique_wallet_24h_change_percent,
-            last_trade_unix_time: data.last_trade_unix_time,
-            last_trade_human_time: data.last_trade_human_time.clone(),
-            supply: data.supply,
-            mc: data.mc,
-            trade_30m: data.trade_30m,
-            trade_history_30m: data.trade_history_30m,
-            trade_30m_change_percent: data.trade_30m_change_percent,
-            sell_30m: data.sell_30m,
-            sell_history_30m: data.sell_history_30m,
-            sell_30m_change_percent: data.sell_30m_change_percent,
-            buy_30m: data.buy_30m,
-            buy_history_30m: data.buy_history_30m,
-            buy_30m_change_percent: data.buy_30m_change_percent,
-            v_30m: data.v_30m,
-            v_30m_usd: data.v_30m_usd,
-            v_history_30m: data.v_history_30m,
-            v_history_30m_usd: data.v_history_30m_usd,
-            v_30m_change_percent: data.v_30m_change_percent,
-            v_buy_30m: data.v_buy_30m,
-            v_buy_30m_usd: data.v_buy_30m_usd,
-            v_buy_history_30m: data.v_buy_history_30m,
-            v_buy_history_30m_usd: data.v_buy_history_30m_usd,
-            v_buy_30m_change_percent: data.v_buy_30m_change_percent,
-            v_sell_30m: data.v_sell_30m,
-            v_sell_30m_usd: data.v_sell_30m_usd,
-            v_sell_history_30m: data.v_sell_history_30m,
-            v_sell_history_30m_usd: data.v_sell_history_30m_usd,
-            v_sell_30m_change_percent: data.v_sell_30m_change_percent,
-            trade_1h: data.trade_1h,
-            trade_history_1h: data.trade_history_1h,
-            trade_1h_change_percent: data.trade_1h_change_percent,
-            sell_1h: data.sell_1h,
-            sell_history_1h: data.sell_history_1h,
-            sell_1h_change_percent: data.sell_1h_change_percent,
-            buy_1h: data.buy_1h,
-            buy_history_1h: data.buy_history_1h,
-            buy_1h_change_percent: data.buy_1h_change_percent,
-            v_1h: data.v_1h,
-            v_1h_usd: data.v_1h_usd,
-            v_history_1h: data.v_history_1h,
-            v_history_1h_usd: data.v_history_1h_usd,
-            v_1h_change_percent: data.v_1h_change_percent,
-            v_buy_1h: data.v_buy_1h,
-            v_buy_1h_usd: data.v_buy_1h_usd,
-            v_buy_history_1h: data.v_buy_history_1h,
-            v_buy_history_1h_usd: data.v_buy_history_1h_usd,
-            v_buy_1h_change_percent: data.v_buy_1h_change_percent,
-            v_sell_1h: data.v_sell_1h,
-            v_sell_1h_usd: data.v_sell_1h_usd,
-            v_sell_history_1h: data.v_sell_history_1h,
-            v_sell_history_1h_usd: data.v_sell_history_1h_usd,
-            v_sell_1h_change_percent: data.v_sell_1h_change_percent,
-            trade_2h: data.trade_2h,
-            trade_history_2h: data.trade_history_2h,
-            trade_2h_change_percent: data.trade_2h_change_percent,
-            sell_2h: data.sell_2h,
-            sell_history_2h: data.sell_history_2h,
-            sell_2h_change_percent: data.sell_2h_change_percent,
-            buy_2h: data.buy_2h,
-            buy_history_2h: data.buy_history_2h,
-            buy_2h_change_percent: data.buy_2h_change_percent,
-            v_2h: data.v_2h,
-            v_2h_usd: data.v_2h_usd,
-            v_history_2h: data.v_history_2h,
-            v_history_2h_usd: data.v_history_2h_usd,
-            v_2h_change_percent: data.v_2h_change_percent,
-            v_buy_2h: data.v_buy_2h,
-            v_buy_2h_usd: data.v_buy_2h_usd,
-            v_buy_history_2h: data.v_buy_history_2h,
-            v_buy_history_2h_usd: data.v_buy_history_2h_usd,
-            v_buy_2h_change_percent: data.v_buy_2h_change_percent,
-            v_sell_2h: data.v_sell_2h,
-            v_sell_2h_usd: data.v_sell_2h_usd,
-            v_sell_history_2h: data.v_sell_history_2h,
-            v_sell_history_2h_usd: data.v_sell_history_2h_usd,
-            v_sell_2h_change_percent: data.v_sell_2h_change_percent,
-            trade_4h: data.trade_4h,
-            trade_history_4h: data.trade_history_4h,
-            trade_4h_change_percent: data.trade_4h_change_percent,
-            sell_4h: data.sell_4h,
-            sell_history_4h: data.sell_history_4h,
-            sell_4h_change_percent: data.sell_4h_change_percent,
-            buy_4h: data.buy_4h,
-            buy_history_4h: data.buy_history_4h,
-            buy_4h_change_percent: data.buy_4h_change_percent,
-            v_4h: data.v_4h,
-            v_4h_usd: data.v_4h_usd,
-            v_history_4h: data.v_history_4h,
-            v_history_4h_usd: data.v_history_4h_usd,
-            v_4h_change_percent: data.v_4h_change_percent,
-            v_buy_4h: data.v_buy_4h,
-            v_buy_4h_usd: data.v_buy_4h_usd,
-            v_buy_history_4h: data.v_buy_history_4h,
-            v_buy_history_4h_usd: data.v_buy_history_4h_usd,
-            v_buy_4h_change_percent: data.v_buy_4h_change_percent,
-            v_sell_4h: data.v_sell_4h,
-            v_sell_4h_usd: data.v_sell_4h_usd,
-            v_sell_history_4h: data.v_sell_history_4h,
-            v_sell_history_4h_usd: data.v_sell_history_4h_usd,
-            v_sell_4h_change_percent: data.v_sell_4h_change_percent,
-            trade_6h: data.trade_6h,
-            trade_history_6h: data.trade_history_6h,
-            trade_6h_change_percent: data.trade_6h_change_percent,
-            sell_6h: data.sell_6h,
-            sell_history_6h: data.sell_history_6h,
-            sell_6h_change_percent: data.sell_6h_change_percent,
-            buy_6h: data.buy_6h,
-            buy_history_6h: data.buy_history_6h,
-            buy_6h_change_percent: data.buy_6h_change_percent,
-            v_6h: data.v_6h,
-            v_6h_usd: data.v_6h_usd,
-            v_history_6h: data.v_history_6h,
-            v_history_6h_usd: data.v_history_6h_usd,
-            v_6h_change_percent: data.v_6h_change_percent,
-            v_buy_6h: data.v_buy_6h,
-            v_buy_6h_usd: data.v_buy_6h_usd,
-            v_buy_history_6h: data.v_buy_history_6h,
-            v_buy_history_6h_usd: data.v_buy_history_6h_usd,
-            v_buy_6h_change_percent: data.v_buy_6h_change_percent,
-            v_sell_6h: data.v_sell_6h,
-            v_sell_6h_usd: data.v_sell_6h_usd,
-            v_sell_history_6h: data.v_sell_history_6h,
-            v_sell_history_6h_usd: data.v_sell_history_6h_usd,
-            v_sell_6h_change_percent: data.v_sell_6h_change_percent,
-            trade_8h: data.trade_8h,
-            trade_history_8h: data.trade_history_8h,
-            trade_8h_change_percent: data.trade_8h_change_percent,
-            sell_8h: data.sell_8h,
-            sell_history_8h: data.sell_history_8h,
-            sell_8h_change_percent: data.sell_8h_change_percent,
-            buy_8h: data.buy_8h,
-            buy_history_8h: data.buy_history_8h,
-            buy_8h_change_percent: data.buy_8h_change_percent,
-            v_8h: data.v_8h,
-            v_8h_usd: data.v_8h_usd,
-            v_history_8h: data.v_history_8h,
-            v_history_8h_usd: data.v_history_8h_usd,
-            v_8h_change_percent: data.v_8h_change_percent,
-            v_buy_8h: data.v_buy_8h,
-            v_buy_8h_usd: data.v_buy_8h_usd,
-            v_buy_history_8h: data.v_buy_history_8h,
-            v_buy_history_8h_usd: data.v_buy_history_8h_usd,
-            v_buy_8h_change_percent: data.v_buy_8h_change_percent,
-            v_sell_8h: data.v_sell_8h,
-            v_sell_8h_usd: data.v_sell_8h_usd,
-            v_sell_history_8h: data.v_sell_history_8h,
-            v_sell_history_8h_usd: data.v_sell_history_8h_usd,
-            v_sell_8h_change_percent: data.v_sell_8h_change_percent,
-            trade_12h: data.trade_12h,
-            trade_history_12h: data.trade_history_12h,
-            trade_12h_change_percent: data.trade_12h_change_percent,
-            sell_12h: data.sell_12h,
-            sell_history_12h: data.sell_history_12h,
-            sell_12h_change_percent: data.sell_12h_change_percent,
-            buy_12h: data.buy_12h,
-            buy_history_12h: data.buy_history_12h,
-            buy_12h_change_percent: data.buy_12h_change_percent,
-            v_12h: data.v_12h,
-            v_12h_usd: data.v_12h_usd,
-            v_history_12h: data.v_history_12h,
-            v_history_12h_usd: data.v_history_12h_usd,
-            v_12h_change_percent: data.v_12h_change_percent,
-            v_buy_12h: data.v_buy_12h,
-            v_buy_12h_usd: data.v_buy_12h_usd,
-            v_buy_history_12h: data.v_buy_history_12h,
-            v_buy_history_12h_usd: data.v_buy_history_12h_usd,
-            v_buy_12h_change_percent: data.v_buy_12h_change_percent,
-            v_sell_12h: data.v_sell_12h,
-            v_sell_12h_usd: data.v_sell_12h_usd,
-            v_sell_history_12h: data.v_sell_history_12h,
-            v_sell_history_12h_usd: data.v_sell_history_12h_usd,
-            v_sell_12h_change_percent: data.v_sell_12h_change_percent,
-            trade_24h: data.trade_24h,
-            trade_history_24h: data.trade_history_24h,
-            trade_24h_change_percent: data.trade_24h_change_percent,
-            sell_24h: data.sell_24h,
-            sell_history_24h: data.sell_history_24h,
-            sell_24h_change_percent: data.sell_24h_change_percent,
-            buy_24h: data.buy_24h,
-            buy_history_24h: data.buy_history_24h,
-            buy_24h_change_percent: data.buy_24h_change_percent,
-            v_24h: data.v_24h,
-            v_24h_usd: data.v_24h_usd,
-            v_history_24h: data.v_history_24h,
-            v_history_24h_usd: data.v_history_24h_usd,
-            v_24h_change_percent: data.v_24h_change_percent,
-            v_buy_24h: data.v_buy_24h,
-            v_buy_24h_usd: data.v_buy_24h_usd,
-            v_buy_history_24h: data.v_buy_history_24h,
-            v_buy_history_24h_usd: data.v_buy_history_24h_usd,
-            v_buy_24h_change_percent: data.v_buy_24h_change_percent,
-            v_sell_24h: data.v_sell_24h,
-            v_sell_24h_usd: data.v_sell_24h_usd,
-            v_sell_history_24h: data.v_sell_history_24h,
-            v_sell_history_24h_usd: data.v_sell_history_24h_usd,
-            v_sell_24h_change_percent: data.v_sell_24h_change_percent,
-            number_markets: data.number_markets,
-            watch: data.watch,
-            view_30m: data.view_30m,
-            view_history_30m: data.view_history_30m,
-            view_30m_change_percent: data.view_30m_change_percent,
-            view_1h: data.view_1h,
-            view_history_1h: data.view_history_1h,
-            view_1h_change_percent: data.view_1h_change_percent,
-            view_2h: data.view_2h,
-            view_history_2h: data.view_history_2h,
-            view_2h_change_percent: data.view_2h_change_percent,
-            view_4h: data.view_4h,
-            view_history_4h: data.view_history_4h,
-            view_4h_change_percent: data.view_4h_change_percent,
-            view_6h: data.view_6h,
-            view_history_6h: data.view_history_6h,
-            view_6h_change_percent: data.view_6h_change_percent,
-            view_8h: data.view_8h,
-            view_history_8h: data.view_history_8h,
-            view_8h_change_percent: data.view_8h_change_percent,
-            view_12h: data.view_12h,
-            view_history_12h: data.view_history_12h,
-            view_12h_change_percent: data.view_12h_change_percent,
-            view_24h: data.view_24h,
-            view_history_24h: data.view_history_24h,
-            view_24h_change_percent: data.view_24h_change_percent,
-            unique_view_30m: data.unique_view_30m,
-            unique_view_history_30m: data.unique_view_history_30m,
-            unique_view_30m_change_percent: data.unique_view_30m_change_percent,
-            unique_view_1h: data.unique_view_1h,
-            unique_view_history_1h: data.unique_view_history_1h,
-            unique_view_1h_change_percent: data.unique_view_1h_change_percent,
-            unique_view_2h: data.unique_view_2h,
-            unique_view_history_2h: data.unique_view_history_2h,
-            unique_view_2h_change_percent: data.unique_view_2h_change_percent,
-            unique_view_4h: data.unique_view_4h,
-            unique_view_history_4h: data.unique_view_history_4h,
-            unique_view_4h_change_percent: data.unique_view_4h_change_percent,
-            unique_view_6h: data.unique_view_6h,
-            unique_view_history_6h: data.unique_view_history_6h,
-            unique_view_6h_change_percent: data.unique_view_6h_change_percent,
-            unique_view_8h: data.unique_view_8h,
-            unique_view_history_8h: data.unique_view_history_8h,
-            unique_view_8h_change_percent: data.unique_view_8h_change_percent,
-            unique_view_12h: data.unique_view_12h,
-            unique_view_history_12h: data.unique_view_history_12h,
-            unique_view_12h_change_percent: data.unique_view_12h_change_percent,
-            unique_view_24h: data.unique_view_24h,
-            unique_view_history_24h: data.unique_view_history_24h,
-            unique_view_24h_change_percent: data.unique_view_24h_change_percent,
-        };
+        // 2. Candidates are resolved independently, so a wallet address before the CA
+        // or a message listing several CAs doesn't lose any token
+        self.resolver.prefetch(&candidates).await;
+        let call_timestamp_ms = message.date().timestamp_millis();
+        let mut token_calls = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            if let Some(token_call) = self.resolver.resolve(candidate, call_timestamp_ms).await {
+                token_calls.push(token_call);
+            }
+        }
+
+        token_calls
+    }
 
-        (token, solana_call_data)
+    fn extract_candidates(&self, content: &MessageContent) -> Vec<ScoredCandidate> {
+        run_extractors(&self.extractors, content)
     }
 }
@@ -0,0 +1,553 @@
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+pub const RAYDIUM_AMM_V4_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const RAYDIUM_CPMM_PROGRAM: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1R";
+pub const RAYDIUM_CLMM_PROGRAM: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+pub const ORCA_WHIRLPOOL_PROGRAM: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+pub const METEORA_DLMM_PROGRAM: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+pub const METEORA_DYNAMIC_PROGRAM: &str = "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB";
+
+/// Anchor discriminators, sha256("account:<Name>")[..8]
+const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+const LB_PAIR_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+const METEORA_POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+const METEORA_VAULT_DISCRIMINATOR: [u8; 8] = [211, 8, 232, 43, 2, 152, 117, 119];
+
+/// Mints a token is usually quoted in. In a pool against one of them the other side is the base.
+pub const QUOTE_MINTS: [&str; 3] = [
+    "So11111111111111111111111111111111111111112", // Wrapped SOL
+    "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", // USDC
+    "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", // USDT
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dex {
+    RaydiumAmmV4,
+    RaydiumCpmm,
+    RaydiumClmm,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+    MeteoraDynamic,
+}
+
+/// A liquidity pool decoded from chain data
+#[derive(Debug, Clone, Serialize)]
+pub struct Pool {
+    pub dex: Dex,
+    pub address: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    /// Token accounts holding the reserves, Meteora vaults for Meteora dynamic pools
+    pub base_vault: String,
+    pub quote_vault: String,
+    /// Raw token amounts at call time, None if the reserve accounts couldn't be read
+    pub base_reserve: Option<u64>,
+    pub quote_reserve: Option<u64>,
+    pub lp_mint: Option<String>,
+    /// Unix timestamp from which the pool accepts swaps
+    pub open_time: Option<u64>,
+}
+
+/// Size of the Raydium AMM v4 `AmmInfo` account, which has no discriminator
+const AMM_INFO_LEN: usize = 752;
+
+/// The fields of Raydium AMM v4 `AmmInfo` we use. Coin is the first side, pc the second.
+pub struct AmmInfo {
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_time: u64,
+    /// PnL the AMM still has to take out of the vaults
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+}
+
+impl AmmInfo {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() != AMM_INFO_LEN {
+            return None;
+        }
+
+        Some(Self {
+            need_take_pnl_coin: read_u64(data, 192)?,
+            need_take_pnl_pc: read_u64(data, 200)?,
+            open_time: read_u64(data, 224)?,
+            coin_vault: read_pubkey(data, 336)?,
+            pc_vault: read_pubkey(data, 368)?,
+            coin_mint: read_pubkey(data, 400)?,
+            pc_mint: read_pubkey(data, 432)?,
+            lp_mint: read_pubkey(data, 464)?,
+        })
+    }
+}
+
+/// Where the reserves of a decoded pool live
+#[derive(Clone)]
+enum ReserveSource {
+    /// Token accounts holding the reserves, and the fees still owed out of them
+    Vaults {
+        vaults: [Pubkey; 2],
+        fees_owed: [u64; 2],
+    },
+    /// Meteora dynamic pools deposit into Meteora vaults and hold vault LP tokens
+    MeteoraVaults {
+        vaults: [Pubkey; 2],
+        vault_lp_accounts: [Pubkey; 2],
+    },
+}
+
+/// A pool whose reserves still need to be read
+#[derive(Clone)]
+pub struct DecodedPool {
+    dex: Dex,
+    address: String,
+    mints: [Pubkey; 2],
+    reserves: ReserveSource,
+    lp_mint: Option<Pubkey>,
+    open_time: Option<u64>,
+}
+
+impl DecodedPool {
+    /// Decodes a pool account owned by one of the supported programs
+    pub fn decode(owner: &str, address: &str, data: &[u8]) -> Option<Self> {
+        let mut lp_mint = None;
+        let mut open_time = None;
+
+        let (dex, mints, reserves) = match owner {
+            RAYDIUM_AMM_V4_PROGRAM => {
+                let amm_info = AmmInfo::decode(data)?;
+                lp_mint = Some(amm_info.lp_mint);
+                open_time = Some(amm_info.open_time);
+                (
+                    Dex::RaydiumAmmV4,
+                    [amm_info.coin_mint, amm_info.pc_mint],
+                    ReserveSource::Vaults {
+                        vaults: [amm_info.coin_vault, amm_info.pc_vault],
+                        fees_owed: [amm_info.need_take_pnl_coin, amm_info.need_take_pnl_pc],
+                    },
+                )
+            }
+            RAYDIUM_CPMM_PROGRAM => {
+                Self::check_discriminator(data, &POOL_STATE_DISCRIMINATOR)?;
+                lp_mint = Some(read_pubkey(data, 136)?);
+                open_time = Some(read_u64(data, 373)?);
+                let fees = |offset: usize| -> Option<u64> {
+                    // protocol fees + fund fees of the side
+                    Some(read_u64(data, offset)? + read_u64(data, offset + 16)?)
+                };
+                (
+                    Dex::RaydiumCpmm,
+                    [read_pubkey(data, 168)?, read_pubkey(data, 200)?],
+                    ReserveSource::Vaults {
+                        vaults: [read_pubkey(data, 72)?, read_pubkey(data, 104)?],
+                        fees_owed: [fees(341)?, fees(349)?],
+                    },
+                )
+            }
+            RAYDIUM_CLMM_PROGRAM => {
+                Self::check_discriminator(data, &POOL_STATE_DISCRIMINATOR)?;
+                (
+                    Dex::RaydiumClmm,
+                    [read_pubkey(data, 73)?, read_pubkey(data, 105)?],
+                    ReserveSource::Vaults {
+                        vaults: [read_pubkey(data, 137)?, read_pubkey(data, 169)?],
+                        fees_owed: [0, 0],
+                    },
+                )
+            }
+            ORCA_WHIRLPOOL_PROGRAM => {
+                Self::check_discriminator(data, &WHIRLPOOL_DISCRIMINATOR)?;
+                (
+                    Dex::OrcaWhirlpool,
+                    [read_pubkey(data, 101)?, read_pubkey(data, 181)?],
+                    ReserveSource::Vaults {
+                        vaults: [read_pubkey(data, 133)?, read_pubkey(data, 213)?],
+                        fees_owed: [read_u64(data, 85)?, read_u64(data, 93)?],
+                    },
+                )
+            }
+            METEORA_DLMM_PROGRAM => {
+                Self::check_discriminator(data, &LB_PAIR_DISCRIMINATOR)?;
+                (
+                    Dex::MeteoraDlmm,
+                    [read_pubkey(data, 88)?, read_pubkey(data, 120)?],
+                    ReserveSource::Vaults {
+                        vaults: [read_pubkey(data, 152)?, read_pubkey(data, 184)?],
+                        fees_owed: [0, 0],
+                    },
+                )
+            }
+            METEORA_DYNAMIC_PROGRAM => {
+                Self::check_discriminator(data, &METEORA_POOL_DISCRIMINATOR)?;
+                (
+                    Dex::MeteoraDynamic,
+                    [read_pubkey(data, 40)?, read_pubkey(data, 72)?],
+                    ReserveSource::MeteoraVaults {
+                        vaults: [read_pubkey(data, 104)?, read_pubkey(data, 136)?],
+                        vault_lp_accounts: [read_pubkey(data, 168)?, read_pubkey(data, 200)?],
+                    },
+                )
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            dex,
+            address: address.to_string(),
+            mints,
+            reserves,
+            lp_mint,
+            open_time,
+        })
+    }
+
+    fn check_discriminator(data: &[u8], discriminator: &[u8; 8]) -> Option<()> {
+        (data.get(..8)? == discriminator).then_some(())
+    }
+
+    /// Reads the reserves from the chain and orders both sides as base and quote
+    pub async fn load(self, solana_client: &RpcClient) -> Pool {
+        let reserves = match self.fetch_reserves(solana_client).await {
+            Ok(reserves) => Some(reserves),
+            Err(e) => {
+                eprintln!(
+                    "Error while fetching reserves of pool: {} \nError: {}",
+                    self.address, e
+                );
+                None
+            }
+        };
+
+        // Pools order their sides by mint address, not base/quote
+        let is_quote = |mint: &Pubkey| QUOTE_MINTS.contains(&mint.to_string().as_str());
+        let (base, quote) = if is_quote(&self.mints[0]) && !is_quote(&self.mints[1]) {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+
+        let vaults = match &self.reserves {
+            ReserveSource::Vaults { vaults, .. } => vaults,
+            ReserveSource::MeteoraVaults { vaults, .. } => vaults,
+        };
+
+        Pool {
+            dex: self.dex,
+            address: self.address,
+            base_mint: self.mints[base].to_string(),
+            quote_mint: self.mints[quote].to_string(),
+            base_vault: vaults[base].to_string(),
+            quote_vault: vaults[quote].to_string(),
+            base_reserve: reserves.map(|reserves| reserves[base]),
+            quote_reserve: reserves.map(|reserves| reserves[quote]),
+            lp_mint: self.lp_mint.map(|lp_mint| lp_mint.to_string()),
+            open_time: self.open_time,
+        }
+    }
+
+    async fn fetch_reserves(
+        &self,
+        solana_client: &RpcClient,
+    ) -> Result<[u64; 2], Box<dyn std::error::Error>> {
+        match &self.reserves {
+            ReserveSource::Vaults { vaults, fees_owed } => {
+                let accounts = solana_client.get_multiple_accounts(vaults).await?;
+                let mut reserves = [0; 2];
+                for side in 0..2 {
+                    let amount = accounts[side]
+                        .as_ref()
+                        .and_then(|account| token_account_amount(&account.data))
+                        .ok_or("vault is not a token account")?;
+                    reserves[side] = amount.saturating_sub(fees_owed[side]);
+                }
+                Ok(reserves)
+            }
+            ReserveSource::MeteoraVaults {
+                vaults,
+                vault_lp_accounts,
+            } => {
+                // 1. Each vault knows its total deposits and its LP mint
+                let vault_accounts = solana_client.get_multiple_accounts(vaults).await?;
+                let mut total_amounts = [0; 2];
+                let mut lp_mints = [Pubkey::default(); 2];
+                for side in 0..2 {
+                    let data = &vault_accounts[side]
+                        .as_ref()
+                        .ok_or("vault account does not exist")?
+                        .data;
+                    Self::check_discriminator(data, &METEORA_VAULT_DISCRIMINATOR)
+                        .ok_or("not a Meteora vault")?;
+                    total_amounts[side] = read_u64(data, 11).ok_or("vault too short")?;
+                    lp_mints[side] = read_pubkey(data, 115).ok_or("vault too short")?;
+                }
+
+                // 2. The pool owns a share of each vault, through its vault LP tokens
+                let lp_accounts = solana_client
+                    .get_multiple_accounts(&[
+                        vault_lp_accounts[0],
+                        vault_lp_accounts[1],
+                        lp_mints[0],
+                        lp_mints[1],
+                    ])
+                    .await?;
+                let mut reserves = [0; 2];
+                for side in 0..2 {
+                    let pool_lp = lp_accounts[side]
+                        .as_ref()
+                        .and_then(|account| token_account_amount(&account.data))
+                        .ok_or("vault LP account is not a token account")?;
+                    let lp_supply = lp_accounts[side + 2]
+                        .as_ref()
+                        .and_then(|account| mint_supply(&account.data))
+                        .ok_or("vault LP mint is not a mint")?;
+                    if lp_supply > 0 {
+                        reserves[side] = (total_amounts[side] as u128 * pool_lp as u128
+                            / lp_supply as u128) as u64;
+                    }
+                }
+                Ok(reserves)
+            }
+        }
+    }
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+pub fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Some(Pubkey::new_from_array(
+        data.get(offset..offset + 32)?.try_into().ok()?,
+    ))
+}
+
+/// `amount` of an SPL token account, the same offset in Token-2022 accounts
+pub fn token_account_amount(data: &[u8]) -> Option<u64> {
+    read_u64(data, 64)
+}
+
+/// `supply` of an SPL mint, the same offset in Token-2022 mints
+pub fn mint_supply(data: &[u8]) -> Option<u64> {
+    read_u64(data, 36)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::hash;
+
+    /// Account data written field by field in declaration order, as Anchor (Borsh) lays it out,
+    /// and the `#[repr(C)]` Raydium AMM v4 struct too since none of its fields need padding.
+    /// Fields the decoders skip are zeroed, so only the offsets of the fields they read are checked.
+    struct Layout(Vec<u8>);
+
+    impl Layout {
+        fn anchor(account: &str) -> Self {
+            Self(discriminator(account).to_vec())
+        }
+
+        fn skip(mut self, len: usize) -> Self {
+            self.0.resize(self.0.len() + len, 0);
+            self
+        }
+
+        fn pubkey(mut self, key: &Pubkey) -> Self {
+            self.0.extend_from_slice(key.as_ref());
+            self
+        }
+
+        fn u64(mut self, value: u64) -> Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        /// Zeroes the rest of the account, `len` bytes long
+        fn padded_to(mut self, len: usize) -> Vec<u8> {
+            assert!(self.0.len() <= len, "layout is longer than the account");
+            self.0.resize(len, 0);
+            self.0
+        }
+    }
+
+    fn discriminator(account: &str) -> [u8; 8] {
+        hash(format!("account:{}", account).as_bytes()).to_bytes()[..8]
+            .try_into()
+            .unwrap()
+    }
+
+    fn keys<const N: usize>() -> [Pubkey; N] {
+        std::array::from_fn(|_| Pubkey::new_unique())
+    }
+
+    fn vaults(pool: &DecodedPool) -> ([Pubkey; 2], [u64; 2]) {
+        match &pool.reserves {
+            ReserveSource::Vaults { vaults, fees_owed } => (*vaults, *fees_owed),
+            ReserveSource::MeteoraVaults { .. } => panic!("expected token vaults"),
+        }
+    }
+
+    #[test]
+    fn discriminators_match_the_anchor_account_names() {
+        assert_eq!(POOL_STATE_DISCRIMINATOR, discriminator("PoolState"));
+        assert_eq!(WHIRLPOOL_DISCRIMINATOR, discriminator("Whirlpool"));
+        assert_eq!(LB_PAIR_DISCRIMINATOR, discriminator("LbPair"));
+        assert_eq!(METEORA_POOL_DISCRIMINATOR, discriminator("Pool"));
+        assert_eq!(METEORA_VAULT_DISCRIMINATOR, discriminator("Vault"));
+    }
+
+    #[test]
+    fn decodes_raydium_amm_v4() {
+        let [coin_vault, pc_vault, coin_mint, pc_mint, lp_mint] = keys();
+        // AmmInfo: 16 u64 parameters, Fees (8 u64), then StateData
+        let data = Layout(Vec::new())
+            .skip(16 * 8 + 8 * 8)
+            .u64(11) // need_take_pnl_coin
+            .u64(22) // need_take_pnl_pc
+            .skip(2 * 8) // total_pnl_pc, total_pnl_coin
+            .u64(1_700_000_000) // pool_open_time
+            .skip(3 * 8 + 4 * 16 + 2 * 8) // punish amounts, init time, swap counters
+            .pubkey(&coin_vault)
+            .pubkey(&pc_vault)
+            .pubkey(&coin_mint)
+            .pubkey(&pc_mint)
+            .pubkey(&lp_mint)
+            .padded_to(AMM_INFO_LEN);
+
+        let pool = DecodedPool::decode(RAYDIUM_AMM_V4_PROGRAM, "amm", &data).unwrap();
+        assert_eq!(pool.dex, Dex::RaydiumAmmV4);
+        assert_eq!(pool.mints, [coin_mint, pc_mint]);
+        assert_eq!(vaults(&pool), ([coin_vault, pc_vault], [11, 22]));
+        assert_eq!(pool.lp_mint, Some(lp_mint));
+        assert_eq!(pool.open_time, Some(1_700_000_000));
+
+        assert!(DecodedPool::decode(RAYDIUM_AMM_V4_PROGRAM, "amm", &data[..700]).is_none());
+    }
+
+    #[test]
+    fn decodes_raydium_cpmm() {
+        let [vault_0, vault_1, lp_mint, mint_0, mint_1] = keys();
+        let data = Layout::anchor("PoolState")
+            .skip(2 * 32) // amm_config, pool_creator
+            .pubkey(&vault_0)
+            .pubkey(&vault_1)
+            .pubkey(&lp_mint)
+            .pubkey(&mint_0)
+            .pubkey(&mint_1)
+            .skip(3 * 32 + 5) // token programs, observation_key, bump, status, decimals
+            .u64(1_000) // lp_supply
+            .u64(1) // protocol_fees_token_0
+            .u64(2) // protocol_fees_token_1
+            .u64(10) // fund_fees_token_0
+            .u64(20) // fund_fees_token_1
+            .u64(1_700_000_000) // open_time
+            .padded_to(637);
+
+        let pool = DecodedPool::decode(RAYDIUM_CPMM_PROGRAM, "cpmm", &data).unwrap();
+        assert_eq!(pool.dex, Dex::RaydiumCpmm);
+        assert_eq!(pool.mints, [mint_0, mint_1]);
+        assert_eq!(vaults(&pool), ([vault_0, vault_1], [11, 22]));
+        assert_eq!(pool.lp_mint, Some(lp_mint));
+        assert_eq!(pool.open_time, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn decodes_raydium_clmm() {
+        let [mint_0, mint_1, vault_0, vault_1] = keys();
+        let data = Layout::anchor("PoolState")
+            .skip(1 + 2 * 32) // bump, amm_config, owner
+            .pubkey(&mint_0)
+            .pubkey(&mint_1)
+            .pubkey(&vault_0)
+            .pubkey(&vault_1)
+            .padded_to(1544);
+
+        let pool = DecodedPool::decode(RAYDIUM_CLMM_PROGRAM, "clmm", &data).unwrap();
+        assert_eq!(pool.dex, Dex::RaydiumClmm);
+        assert_eq!(pool.mints, [mint_0, mint_1]);
+        assert_eq!(vaults(&pool), ([vault_0, vault_1], [0, 0]));
+    }
+
+    #[test]
+    fn decodes_orca_whirlpool() {
+        let [mint_a, vault_a, mint_b, vault_b] = keys();
+        let data = Layout::anchor("Whirlpool")
+            .skip(32 + 1 + 2 + 2 + 2 + 2) // config, bump, tick spacing and seed, fee rates
+            .skip(16 + 16 + 4) // liquidity, sqrt_price, tick_current_index
+            .u64(5) // protocol_fee_owed_a
+            .u64(6) // protocol_fee_owed_b
+            .pubkey(&mint_a)
+            .pubkey(&vault_a)
+            .skip(16) // fee_growth_global_a
+            .pubkey(&mint_b)
+            .pubkey(&vault_b)
+            .padded_to(653);
+
+        let pool = DecodedPool::decode(ORCA_WHIRLPOOL_PROGRAM, "whirlpool", &data).unwrap();
+        assert_eq!(pool.dex, Dex::OrcaWhirlpool);
+        assert_eq!(pool.mints, [mint_a, mint_b]);
+        assert_eq!(vaults(&pool), ([vault_a, vault_b], [5, 6]));
+    }
+
+    #[test]
+    fn decodes_meteora_dlmm() {
+        let [mint_x, mint_y, reserve_x, reserve_y] = keys();
+        let data = Layout::anchor("LbPair")
+            .skip(32 + 32) // StaticParameters, VariableParameters
+            .skip(1 + 2 + 1 + 4 + 2 + 1 + 1 + 2 + 1 + 1) // bump to padding0
+            .pubkey(&mint_x)
+            .pubkey(&mint_y)
+            .pubkey(&reserve_x)
+            .pubkey(&reserve_y)
+            .padded_to(904);
+
+        let pool = DecodedPool::decode(METEORA_DLMM_PROGRAM, "dlmm", &data).unwrap();
+        assert_eq!(pool.dex, Dex::MeteoraDlmm);
+        assert_eq!(pool.mints, [mint_x, mint_y]);
+        assert_eq!(vaults(&pool), ([reserve_x, reserve_y], [0, 0]));
+    }
+
+    #[test]
+    fn decodes_meteora_dynamic() {
+        let [lp_mint, mint_a, mint_b, vault_a, vault_b, vault_lp_a, vault_lp_b] = keys();
+        let data = Layout::anchor("Pool")
+            .pubkey(&lp_mint)
+            .pubkey(&mint_a)
+            .pubkey(&mint_b)
+            .pubkey(&vault_a)
+            .pubkey(&vault_b)
+            .pubkey(&vault_lp_a)
+            .pubkey(&vault_lp_b)
+            .padded_to(944);
+
+        let pool = DecodedPool::decode(METEORA_DYNAMIC_PROGRAM, "dynamic", &data).unwrap();
+        assert_eq!(pool.dex, Dex::MeteoraDynamic);
+        assert_eq!(pool.mints, [mint_a, mint_b]);
+        match pool.reserves {
+            ReserveSource::MeteoraVaults {
+                vaults,
+                vault_lp_accounts,
+            } => {
+                assert_eq!(vaults, [vault_a, vault_b]);
+                assert_eq!(vault_lp_accounts, [vault_lp_a, vault_lp_b]);
+            }
+            ReserveSource::Vaults { .. } => panic!("expected Meteora vaults"),
+        }
+    }
+
+    #[test]
+    fn rejects_accounts_of_another_type() {
+        let data = Layout::anchor("AmmConfig").padded_to(637);
+
+        assert!(DecodedPool::decode(RAYDIUM_CPMM_PROGRAM, "config", &data).is_none());
+        assert!(DecodedPool::decode(ORCA_WHIRLPOOL_PROGRAM, "config", &data).is_none());
+        assert!(DecodedPool::decode("11111111111111111111111111111111", "x", &data).is_none());
+    }
+}
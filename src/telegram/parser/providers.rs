@@ -0,0 +1,885 @@
+use super::birdeye_structs::{SolanaTokenOverview, SolanaTokenOverviewResponse};
+use super::{
+    BondingCurve, Chain, DexscreenerSearchPair, DexscreenerSearchResponse, JupiterPriceResponse,
+    Pool, SolanaTokenCall, SolanaTokenOverviewData, Token,
+};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::env;
+
+const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Every provider in their default priority order
+pub const DEFAULT_MARKET_DATA_PROVIDERS: &str = "birdeye,dexscreener,jupiter,onchain";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketDataProvider {
+    Birdeye,
+    Dexscreener,
+    Jupiter,
+    OnChain,
+}
+
+impl MarketDataProvider {
+    /// Id used in `MARKET_DATA_PROVIDERS`
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Birdeye => "birdeye",
+            Self::Dexscreener => "dexscreener",
+            Self::Jupiter => "jupiter",
+            Self::OnChain => "onchain",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "birdeye" => Some(Self::Birdeye),
+            "dexscreener" => Some(Self::Dexscreener),
+            "jupiter" => Some(Self::Jupiter),
+            "onchain" => Some(Self::OnChain),
+            _ => None,
+        }
+    }
+}
+
+/// Market data of a token at call time, the same fields whichever provider supplied it
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketSnapshot {
+    pub provider: MarketDataProvider,
+    pub price_usd: Option<f64>,
+    /// Price in the pool's quote token, set when it was computed from reserves
+    pub price_native: Option<f64>,
+    pub quote_mint: Option<String>,
+    pub liquidity_usd: Option<f64>,
+    pub market_cap_usd: Option<f64>,
+    pub volume_24h_usd: Option<f64>,
+    pub price_change_24h_percent: Option<f64>,
+}
+
+/// What a provider returns for a token it knows
+pub struct MarketData {
+    pub snapshot: MarketSnapshot,
+    /// Name, symbol and decimals, from providers that have them
+    pub token: Option<Token>,
+    /// Birdeye's detailed breakdown, only Birdeye has one
+    pub token_call_data: Option<SolanaTokenCall>,
+}
+
+/// What the resolver knows about the token before asking for market data
+pub struct MarketDataRequest<'a> {
+    pub token_address: &'a str,
+    pub chain: Chain,
+    /// The pool the call pointed to, with its reserves
+    pub pool: Option<&'a Pool>,
+    pub bonding_curve: Option<&'a BondingCurve>,
+    /// Decimals of the token, known for Solana mints
+    pub decimals: Option<u8>,
+    /// Dexscreener pairs of the token the resolver already fetched, reused instead of
+    /// asking Dexscreener again
+    pub token_pairs: Option<&'a [DexscreenerSearchPair]>,
+}
+
+/// A source of market data. Providers are tried in the order of `MARKET_DATA_PROVIDERS`
+/// until one knows the token.
+pub trait TokenDataProvider: Send + Sync {
+    fn kind(&self) -> MarketDataProvider;
+
+    /// Ok(None) when the provider doesn't know the token or doesn't support its chain
+    fn fetch<'a>(
+        &'a self,
+        request: &'a MarketDataRequest<'a>,
+    ) -> BoxFuture<'a, Result<Option<MarketData>, Box<dyn std::error::Error>>>;
+}
+
+/// Builds the providers from ids like `birdeye,jupiter`, in that priority order
+pub fn build_providers(
+    ids: &[String],
+    reqwest_client: &reqwest::Client,
+) -> Result<Vec<Box<dyn TokenDataProvider>>, Box<dyn std::error::Error>> {
+    let mut providers: Vec<Box<dyn TokenDataProvider>> = Vec::new();
+
+    for id in ids {
+        let kind = MarketDataProvider::from_id(id)
+            .ok_or_else(|| format!("Unknown market data provider: {}", id))?;
+        let provider: Box<dyn TokenDataProvider> = match kind {
+            MarketDataProvider::Birdeye => Box::new(BirdeyeProvider {
+                reqwest_client: reqwest_client.clone(),
+                api_key: env::var("BIRDEYE_API_KEY")
+                    .map_err(|_| "BIRDEYE_API_KEY must be set to use the birdeye provider")?,
+            }),
+            MarketDataProvider::Dexscreener => Box::new(DexscreenerProvider {
+                reqwest_client: reqwest_client.clone(),
+            }),
+            MarketDataProvider::Jupiter => Box::new(JupiterProvider {
+                reqwest_client: reqwest_client.clone(),
+            }),
+            MarketDataProvider::OnChain => Box::new(OnChainProvider),
+        };
+        providers.push(provider);
+    }
+
+    Ok(providers)
+}
+
+/// Pairs of a token on every chain Dexscreener supports
+pub async fn fetch_token_pairs(
+    reqwest_client: &reqwest::Client,
+    token_address: &str,
+) -> Result<Vec<DexscreenerSearchPair>, Box<dyn std::error::Error>> {
+    let dexscreener_tokens_query = format!(
+        "https://api.dexscreener.com/latest/dex/tokens/{}",
+        token_address
+    );
+
+    let dexscreener_tokens_response: DexscreenerSearchResponse = reqwest_client
+        .get(&dexscreener_tokens_query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(dexscreener_tokens_response.pairs.unwrap_or_default())
+}
+
+/// Token overview from Birdeye, the most complete source but rate limited by the API key
+pub struct BirdeyeProvider {
+    reqwest_client: reqwest::Client,
+    api_key: String,
+}
+
+impl TokenDataProvider for BirdeyeProvider {
+    fn kind(&self) -> MarketDataProvider {
+        MarketDataProvider::Birdeye
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        request: &'a MarketDataRequest<'a>,
+    ) -> BoxFuture<'a, Result<Option<MarketData>, Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let data = match self
+                .fetch_token_overview(request.token_address, request.chain)
+                .await?
+            {
+                Some(data) => data,
+                None => return Ok(None),
+            };
+
+            let snapshot = MarketSnapshot {
+                provider: MarketDataProvider::Birdeye,
+                price_usd: Some(data.price),
+                price_native: None,
+                quote_mint: None,
+                liquidity_usd: data.liquidity,
+                market_cap_usd: data.mc,
+                volume_24h_usd: data.v_24h_usd,
+                price_change_24h_percent: data.price_change_24h_percent,
+            };
+            let (token, token_call_data) = Self::format_token_data(data, request.chain);
+
+            Ok(Some(MarketData {
+                snapshot,
+                token: Some(token),
+                token_call_data: Some(token_call_data),
+            }))
+        })
+    }
+}
+
+impl BirdeyeProvider {
+    async fn fetch_token_overview(
+        &self,
+        token_address: &str,
+        chain: Chain,
+    ) -> Result<Option<SolanaTokenOverview>, Box<dyn std::error::Error>> {
+        let birdeye_token_overview_query = format!(
+            "https://public-api.birdeye.so/defi/token_overview?address={}",
+            token_address
+        );
+
+        let token_overview_response: SolanaTokenOverviewResponse = self
+            .reqwest_client
+            .get(&birdeye_token_overview_query)
+            .header("x-chain", chain.id())
+            .header("X-API-KEY", &self.api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if !token_overview_response.success {
+            return Ok(None);
+        }
+
+        match token_overview_response.data {
+            SolanaTokenOverviewData::Data(data) => Ok(Some(*data)),
+            SolanaTokenOverviewData::Empty(_) => Ok(None),
+        }
+    }
+
+    fn format_token_data(data: SolanaTokenOverview, chain: Chain) -> (Token, SolanaTokenCall) {
+        let token = Token {
+            address: data.address.clone(),
+            decimals: Some(data.decimals),
+            name: data.name.clone(),
+            symbol: data.symbol.clone(),
+            network: chain.id().to_string(),
+            logo_uri: data.logo_uri.clone(),
+            extensions: data.extensions,
+            token_2022: None,
+            risk_flags: Vec::new(),
+            update_authority: None,
+            market_data_pending: false,
+        };
+
+        let solana_call_data = SolanaTokenCall {
+            liquidity: data.liquidity,
+            price: data.price,
+            history_30m_price: data.history_30m_price,
+            price_change_30m_percent: data.price_change_30m_percent,
+            history_1h_price: data.history_1h_price,
+            price_change_1h_percent: data.price_change_1h_percent,
+            history_2h_price: data.history_2h_price,
+            price_change_2h_percent: data.price_change_2h_percent,
+            history_4h_price: data.history_4h_price,
+            price_change_4h_percent: data.price_change_4h_percent,
+            history_6h_price: data.history_6h_price,
+            price_change_6h_percent: data.price_change_6h_percent,
+            history_8h_price: data.history_8h_price,
+            price_change_8h_percent: data.price_change_8h_percent,
+            history_12h_price: data.history_12h_price,
+            price_change_12h_percent: data.price_change_12h_percent,
+            history_24h_price: data.history_24h_price,
+            price_change_24h_percent: data.price_change_24h_percent,
+            unique_wallet_30m: data.unique_wallet_30m,
+            unique_wallet_history_30m: data.unique_wallet_history_30m,
+            unique_wallet_30m_change_percent: data.unique_wallet_30m_change_percent,
+            unique_wallet_1h: data.unique_wallet_1h,
+            unique_wallet_history_1h: data.unique_wallet_history_1h,
+            unique_wallet_1h_change_percent: data.unique_wallet_1h_change_percent,
+            unique_wallet_2h: data.unique_wallet_2h,
+            unique_wallet_history_2h: data.unique_wallet_history_2h,
+            unique_wallet_2h_change_percent: data.unique_wallet_2h_change_percent,
+            unique_wallet_4h: data.unique_wallet_4h,
+            unique_wallet_history_4h: data.unique_wallet_history_4h,
+            unique_wallet_4h_change_percent: data.unique_wallet_4h_change_percent,
+            unique_wallet_6h: data.unique_wallet_6h,
+            unique_wallet_history_6h: data.unique_wallet_history_6h,
+            unique_wallet_6h_change_percent: data.unique_wallet_6h_change_percent,
+            unique_wallet_8h: data.unique_wallet_8h,
+            unique_wallet_history_8h: data.unique_wallet_history_8h,
+            unique_wallet_8h_change_percent: data.unique_wallet_8h_change_percent,
+            unique_wallet_12h: data.unique_wallet_12h,
+            unique_wallet_history_12h: data.unique_wallet_history_12h,
+            unique_wallet_12h_change_percent: data.unique_wallet_12h_change_percent,
+            unique_wallet_24h: data.unique_wallet_24h,
+            unique_wallet_history_24h: data.unique_wallet_history_24h,
+            unique_wallet_24h_change_percent: data.unique_wallet_24h_change_percent,
+            last_trade_unix_time: data.last_trade_unix_time,
+            last_trade_human_time: data.last_trade_human_time.clone(),
+            supply: data.supply,
+            mc: data.mc,
+            trade_30m: data.trade_30m,
+            trade_history_30m: data.trade_history_30m,
+            trade_30m_change_percent: data.trade_30m_change_percent,
+            sell_30m: data.sell_30m,
+            sell_history_30m: data.sell_history_30m,
+            sell_30m_change_percent: data.sell_30m_change_percent,
+            buy_30m: data.buy_30m,
+            buy_history_30m: data.buy_history_30m,
+            buy_30m_change_percent: data.buy_30m_change_percent,
+            v_30m: data.v_30m,
+            v_30m_usd: data.v_30m_usd,
+            v_history_30m: data.v_history_30m,
+            v_history_30m_usd: data.v_history_30m_usd,
+            v_30m_change_percent: data.v_30m_change_percent,
+            v_buy_30m: data.v_buy_30m,
+            v_buy_30m_usd: data.v_buy_30m_usd,
+            v_buy_history_30m: data.v_buy_history_30m,
+            v_buy_history_30m_usd: data.v_buy_history_30m_usd,
+            v_buy_30m_change_percent: data.v_buy_30m_change_percent,
+            v_sell_30m: data.v_sell_30m,
+            v_sell_30m_usd: data.v_sell_30m_usd,
+            v_sell_history_30m: data.v_sell_history_30m,
+            v_sell_history_30m_usd: data.v_sell_history_30m_usd,
+            v_sell_30m_change_percent: data.v_sell_30m_change_percent,
+            trade_1h: data.trade_1h,
+            trade_history_1h: data.trade_history_1h,
+            trade_1h_change_percent: data.trade_1h_change_percent,
+            sell_1h: data.sell_1h,
+            sell_history_1h: data.sell_history_1h,
+            sell_1h_change_percent: data.sell_1h_change_percent,
+            buy_1h: data.buy_1h,
+            buy_history_1h: data.buy_history_1h,
+            buy_1h_change_percent: data.buy_1h_change_percent,
+            v_1h: data.v_1h,
+            v_1h_usd: data.v_1h_usd,
+            v_history_1h: data.v_history_1h,
+            v_history_1h_usd: data.v_history_1h_usd,
+            v_1h_change_percent: data.v_1h_change_percent,
+            v_buy_1h: data.v_buy_1h,
+            v_buy_1h_usd: data.v_buy_1h_usd,
+            v_buy_history_1h: data.v_buy_history_1h,
+            v_buy_history_1h_usd: data.v_buy_history_1h_usd,
+            v_buy_1h_change_percent: data.v_buy_1h_change_percent,
+            v_sell_1h: data.v_sell_1h,
+            v_sell_1h_usd: data.v_sell_1h_usd,
+            v_sell_history_1h: data.v_sell_history_1h,
+            v_sell_history_1h_usd: data.v_sell_history_1h_usd,
+            v_sell_1h_change_percent: data.v_sell_1h_change_percent,
+            trade_2h: data.trade_2h,
+            trade_history_2h: data.trade_history_2h,
+            trade_2h_change_percent: data.trade_2h_change_percent,
+            sell_2h: data.sell_2h,
+            sell_history_2h: data.sell_history_2h,
+            sell_2h_change_percent: data.sell_2h_change_percent,
+            buy_2h: data.buy_2h,
+            buy_history_2h: data.buy_history_2h,
+            buy_2h_change_percent: data.buy_2h_change_percent,
+            v_2h: data.v_2h,
+            v_2h_usd: data.v_2h_usd,
+            v_history_2h: data.v_history_2h,
+            v_history_2h_usd: data.v_history_2h_usd,
+            v_2h_change_percent: data.v_2h_change_percent,
+            v_buy_2h: data.v_buy_2h,
+            v_buy_2h_usd: data.v_buy_2h_usd,
+            v_buy_history_2h: data.v_buy_history_2h,
+            v_buy_history_2h_usd: data.v_buy_history_2h_usd,
+            v_buy_2h_change_percent: data.v_buy_2h_change_percent,
+            v_sell_2h: data.v_sell_2h,
+            v_sell_2h_usd: data.v_sell_2h_usd,
+            v_sell_history_2h: data.v_sell_history_2h,
+            v_sell_history_2h_usd: data.v_sell_history_2h_usd,
+            v_sell_2h_change_percent: data.v_sell_2h_change_percent,
+            trade_4h: data.trade_4h,
+            trade_history_4h: data.trade_history_4h,
+            trade_4h_change_percent: data.trade_4h_change_percent,
+            sell_4h: data.sell_4h,
+            sell_history_4h: data.sell_history_4h,
+            sell_4h_change_percent: data.sell_4h_change_percent,
+            buy_4h: data.buy_4h,
+            buy_history_4h: data.buy_history_4h,
+            buy_4h_change_percent: data.buy_4h_change_percent,
+            v_4h: data.v_4h,
+            v_4h_usd: data.v_4h_usd,
+            v_history_4h: data.v_history_4h,
+            v_history_4h_usd: data.v_history_4h_usd,
+            v_4h_change_percent: data.v_4h_change_percent,
+            v_buy_4h: data.v_buy_4h,
+            v_buy_4h_usd: data.v_buy_4h_usd,
+            v_buy_history_4h: data.v_buy_history_4h,
+            v_buy_history_4h_usd: data.v_buy_history_4h_usd,
+            v_buy_4h_change_percent: data.v_buy_4h_change_percent,
+            v_sell_4h: data.v_sell_4h,
+            v_sell_4h_usd: data.v_sell_4h_usd,
+            v_sell_history_4h: data.v_sell_history_4h,
+            v_sell_history_4h_usd: data.v_sell_history_4h_usd,
+            v_sell_4h_change_percent: data.v_sell_4h_change_percent,
+            trade_6h: data.trade_6h,
+            trade_history_6h: data.trade_history_6h,
+            trade_6h_change_percent: data.trade_6h_change_percent,
+            sell_6h: data.sell_6h,
+            sell_history_6h: data.sell_history_6h,
+            sell_6h_change_percent: data.sell_6h_change_percent,
+            buy_6h: data.buy_6h,
+            buy_history_6h: data.buy_history_6h,
+            buy_6h_change_percent: data.buy_6h_change_percent,
+            v_6h: data.v_6h,
+            v_6h_usd: data.v_6h_usd,
+            v_history_6h: data.v_history_6h,
+            v_history_6h_usd: data.v_history_6h_usd,
+            v_6h_change_percent: data.v_6h_change_percent,
+            v_buy_6h: data.v_buy_6h,
+            v_buy_6h_usd: data.v_buy_6h_usd,
+            v_buy_history_6h: data.v_buy_history_6h,
+            v_buy_history_6h_usd: data.v_buy_history_6h_usd,
+            v_buy_6h_change_percent: data.v_buy_6h_change_percent,
+            v_sell_6h: data.v_sell_6h,
+            v_sell_6h_usd: data.v_sell_6h_usd,
+            v_sell_history_6h: data.v_sell_history_6h,
+            v_sell_history_6h_usd: data.v_sell_history_6h_usd,
+            v_sell_6h_change_percent: data.v_sell_6h_change_percent,
+            trade_8h: data.trade_8h,
+            trade_history_8h: data.trade_history_8h,
+            trade_8h_change_percent: data.trade_8h_change_percent,
+            sell_8h: data.sell_8h,
+            sell_history_8h: data.sell_history_8h,
+            sell_8h_change_percent: data.sell_8h_change_percent,
+            buy_8h: data.buy_8h,
+            buy_history_8h: data.buy_history_8h,
+            buy_8h_change_percent: data.buy_8h_change_percent,
+            v_8h: data.v_8h,
+            v_8h_usd: data.v_8h_usd,
+            v_history_8h: data.v_history_8h,
+            v_history_8h_usd: data.v_history_8h_usd,
+            v_8h_change_percent: data.v_8h_change_percent,
+            v_buy_8h: data.v_buy_8h,
+            v_buy_8h_usd: data.v_buy_8h_usd,
+            v_buy_history_8h: data.v_buy_history_8h,
+            v_buy_history_8h_usd: data.v_buy_history_8h_usd,
+            v_buy_8h_change_percent: data.v_buy_8h_change_percent,
+            v_sell_8h: data.v_sell_8h,
+            v_sell_8h_usd: data.v_sell_8h_usd,
+            v_sell_history_8h: data.v_sell_history_8h,
+            v_sell_history_8h_usd: data.v_sell_history_8h_usd,
+            v_sell_8h_change_percent: data.v_sell_8h_change_percent,
+            trade_12h: data.trade_12h,
+            trade_history_12h: data.trade_history_12h,
+            trade_12h_change_percent: data.trade_12h_change_percent,
+            sell_12h: data.sell_12h,
+            sell_history_12h: data.sell_history_12h,
+            sell_12h_change_percent: data.sell_12h_change_percent,
+            buy_12h: data.buy_12h,
+            buy_history_12h: data.buy_history_12h,
+            buy_12h_change_percent: data.buy_12h_change_percent,
+            v_12h: data.v_12h,
+            v_12h_usd: data.v_12h_usd,
+            v_history_12h: data.v_history_12h,
+            v_history_12h_usd: data.v_history_12h_usd,
+            v_12h_change_percent: data.v_12h_change_percent,
+            v_buy_12h: data.v_buy_12h,
+            v_buy_12h_usd: data.v_buy_12h_usd,
+            v_buy_history_12h: data.v_buy_history_12h,
+            v_buy_history_12h_usd: data.v_buy_history_12h_usd,
+            v_buy_12h_change_percent: data.v_buy_12h_change_percent,
+            v_sell_12h: data.v_sell_12h,
+            v_sell_12h_usd: data.v_sell_12h_usd,
+            v_sell_history_12h: data.v_sell_history_12h,
+            v_sell_history_12h_usd: data.v_sell_history_12h_usd,
+            v_sell_12h_change_percent: data.v_sell_12h_change_percent,
+            trade_24h: data.trade_24h,
+            trade_history_24h: data.trade_history_24h,
+            trade_24h_change_percent: data.trade_24h_change_percent,
+            sell_24h: data.sell_24h,
+            sell_history_24h: data.sell_history_24h,
+            sell_24h_change_percent: data.sell_24h_change_percent,
+            buy_24h: data.buy_24h,
+            buy_history_24h: data.buy_history_24h,
+            buy_24h_change_percent: data.buy_24h_change_percent,
+            v_24h: data.v_24h,
+            v_24h_usd: data.v_24h_usd,
+            v_history_24h: data.v_history_24h,
+            v_history_24h_usd: data.v_history_24h_usd,
+            v_24h_change_percent: data.v_24h_change_percent,
+            v_buy_24h: data.v_buy_24h,
+            v_buy_24h_usd: data.v_buy_24h_usd,
+            v_buy_history_24h: data.v_buy_history_24h,
+            v_buy_history_24h_usd: data.v_buy_history_24h_usd,
+            v_buy_24h_change_percent: data.v_buy_24h_change_percent,
+            v_sell_24h: data.v_sell_24h,
+            v_sell_24h_usd: data.v_sell_24h_usd,
+            v_sell_history_24h: data.v_sell_history_24h,
+            v_sell_history_24h_usd: data.v_sell_history_24h_usd,
+            v_sell_24h_change_percent: data.v_sell_24h_change_percent,
+            number_markets: data.number_markets,
+            watch: data.watch,
+            view_30m: data.view_30m,
+            view_history_30m: data.view_history_30m,
+            view_30m_change_percent: data.view_30m_change_percent,
+            view_1h: data.view_1h,
+            view_history_1h: data.view_history_1h,
+            view_1h_change_percent: data.view_1h_change_percent,
+            view_2h: data.view_2h,
+            view_history_2h: data.view_history_2h,
+            view_2h_change_percent: data.view_2h_change_percent,
+            view_4h: data.view_4h,
+            view_history_4h: data.view_history_4h,
+            view_4h_change_percent: data.view_4h_change_percent,
+            view_6h: data.view_6h,
+            view_history_6h: data.view_history_6h,
+            view_6h_change_percent: data.view_6h_change_percent,
+            view_8h: data.view_8h,
+            view_history_8h: data.view_history_8h,
+            view_8h_change_percent: data.view_8h_change_percent,
+            view_12h: data.view_12h,
+            view_history_12h: data.view_history_12h,
+            view_12h_change_percent: data.view_12h_change_percent,
+            view_24h: data.view_24h,
+            view_history_24h: data.view_history_24h,
+            view_24h_change_percent: data.view_24h_change_percent,
+            unique_view_30m: data.unique_view_30m,
+            unique_view_history_30m: data.unique_view_history_30m,
+            unique_view_30m_change_percent: data.unique_view_30m_change_percent,
+            unique_view_1h: data.unique_view_1h,
+            unique_view_history_1h: data.unique_view_history_1h,
+            unique_view_1h_change_percent: data.unique_view_1h_change_percent,
+            unique_view_2h: data.unique_view_2h,
+            unique_view_history_2h: data.unique_view_history_2h,
+            unique_view_2h_change_percent: data.unique_view_2h_change_percent,
+            unique_view_4h: data.unique_view_4h,
+            unique_view_history_4h: data.unique_view_history_4h,
+            unique_view_4h_change_percent: data.unique_view_4h_change_percent,
+            unique_view_6h: data.unique_view_6h,
+            unique_view_history_6h: data.unique_view_history_6h,
+            unique_view_6h_change_percent: data.unique_view_6h_change_percent,
+            unique_view_8h: data.unique_view_8h,
+            unique_view_history_8h: data.unique_view_history_8h,
+            unique_view_8h_change_percent: data.unique_view_8h_change_percent,
+            unique_view_12h: data.unique_view_12h,
+            unique_view_history_12h: data.unique_view_history_12h,
+            unique_view_12h_change_percent: data.unique_view_12h_change_percent,
+            unique_view_24h: data.unique_view_24h,
+            unique_view_history_24h: data.unique_view_history_24h,
+            unique_view_24h_change_percent: data.unique_view_24h_change_percent,
+        };
+
+        (token, solana_call_data)
+    }
+}
+
+/// Dexscreener token pairs, priced from the most liquid pair on the token's chain
+pub struct DexscreenerProvider {
+    reqwest_client: reqwest::Client,
+}
+
+impl TokenDataProvider for DexscreenerProvider {
+    fn kind(&self) -> MarketDataProvider {
+        MarketDataProvider::Dexscreener
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        request: &'a MarketDataRequest<'a>,
+    ) -> BoxFuture<'a, Result<Option<MarketData>, Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let fetched_pairs;
+            let pairs = match request.token_pairs {
+                Some(pairs) => pairs,
+                None => {
+                    fetched_pairs =
+                        fetch_token_pairs(&self.reqwest_client, request.token_address).await?;
+                    &fetched_pairs
+                }
+            };
+            let liquidity = |pair: &DexscreenerSearchPair| {
+                pair.liquidity
+                    .as_ref()
+                    .and_then(|liquidity| liquidity.usd)
+                    .unwrap_or(0.0)
+            };
+            let pair = match pairs
+                .iter()
+                .filter(|pair| pair.chain_id == request.chain.id())
+                .filter(|pair| {
+                    pair.base_token
+                        .address
+                        .eq_ignore_ascii_case(request.token_address)
+                })
+                .max_by(|a, b| liquidity(a).total_cmp(&liquidity(b)))
+            {
+                Some(pair) => pair,
+                None => return Ok(None),
+            };
+
+            let snapshot = MarketSnapshot {
+                provider: MarketDataProvider::Dexscreener,
+                price_usd: pair.price_usd.as_ref().and_then(|price| price.parse().ok()),
+                price_native: None,
+                quote_mint: None,
+                liquidity_usd: pair.liquidity.as_ref().and_then(|liquidity| liquidity.usd),
+                market_cap_usd: pair.market_cap,
+                volume_24h_usd: pair.volume.as_ref().and_then(|volume| volume.h24),
+                price_change_24h_percent: pair.price_change.as_ref().and_then(|change| change.h24),
+            };
+            let token = Token {
+                address: pair.base_token.address.clone(),
+                decimals: request.decimals,
+                name: pair.base_token.name.clone(),
+                symbol: pair.base_token.symbol.clone(),
+                network: request.chain.id().to_string(),
+                logo_uri: None,
+                extensions: None,
+                token_2022: None,
+                risk_flags: Vec::new(),
+                update_authority: None,
+                market_data_pending: false,
+            };
+
+            Ok(Some(MarketData {
+                snapshot,
+                token: Some(token),
+                token_call_data: None,
+            }))
+        })
+    }
+}
+
+/// Jupiter's USD price, Solana only
+pub struct JupiterProvider {
+    reqwest_client: reqwest::Client,
+}
+
+impl TokenDataProvider for JupiterProvider {
+    fn kind(&self) -> MarketDataProvider {
+        MarketDataProvider::Jupiter
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        request: &'a MarketDataRequest<'a>,
+    ) -> BoxFuture<'a, Result<Option<MarketData>, Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            if request.chain != Chain::Solana {
+                return Ok(None);
+            }
+
+            let jupiter_price_query = format!(
+                "https://lite-api.jup.ag/price/v3?ids={}",
+                request.token_address
+            );
+            let mut jupiter_price_response: JupiterPriceResponse = self
+                .reqwest_client
+                .get(&jupiter_price_query)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            Ok(jupiter_price_response
+                .remove(request.token_address)
+                .map(|price| MarketData {
+                    snapshot: MarketSnapshot {
+                        provider: MarketDataProvider::Jupiter,
+                        price_usd: Some(price.usd_price),
+                        price_native: None,
+                        quote_mint: None,
+                        liquidity_usd: None,
+                        market_cap_usd: None,
+                        volume_24h_usd: None,
+                        price_change_24h_percent: price.price_change_24h,
+                    },
+                    token: None,
+                    token_call_data: None,
+                }))
+        })
+    }
+}
+
+/// Prices from the reserves of the pool or bonding curve the call pointed to.
+/// Needs no request, but only knows tokens called through a pool or a pump.fun mint.
+pub struct OnChainProvider;
+
+impl OnChainProvider {
+    /// Decimals of the quote mints pools are priced in, and whether they are USD stablecoins
+    fn quote_decimals(mint: &str) -> Option<(u8, bool)> {
+        match mint {
+            WRAPPED_SOL_MINT => Some((9, false)),
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v" => Some((6, true)), // USDC
+            "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB" => Some((6, true)), // USDT
+            _ => None,
+        }
+    }
+
+    fn pool_snapshot(pool: &Pool, token_address: &str, decimals: u8) -> Option<MarketSnapshot> {
+        if pool.base_mint != token_address {
+            return None;
+        }
+        let (quote_decimals, is_stable) = Self::quote_decimals(&pool.quote_mint)?;
+        let base_reserve = pool.base_reserve? as f64 / 10f64.powi(decimals as i32);
+        let quote_reserve = pool.quote_reserve? as f64 / 10f64.powi(quote_decimals as i32);
+        if base_reserve == 0.0 {
+            return None;
+        }
+
+        let price_native = quote_reserve / base_reserve;
+        Some(MarketSnapshot {
+            provider: MarketDataProvider::OnChain,
+            price_usd: is_stable.then_some(price_native),
+            price_native: Some(price_native),
+            quote_mint: Some(pool.quote_mint.clone()),
+            liquidity_usd: is_stable.then_some(quote_reserve * 2.0),
+            market_cap_usd: None,
+            volume_24h_usd: None,
+            price_change_24h_percent: None,
+        })
+    }
+
+    /// Pump.fun prices from the virtual reserves, 9 decimals of SOL for 6 of the token
+    fn curve_snapshot(bonding_curve: &BondingCurve) -> Option<MarketSnapshot> {
+        if bonding_curve.graduated || bonding_curve.virtual_token_reserves == 0 {
+            return None;
+        }
+
+        let sol_reserves = bonding_curve.virtual_sol_reserves as f64 / 1e9;
+        let token_reserves = bonding_curve.virtual_token_reserves as f64 / 1e6;
+        Some(MarketSnapshot {
+            provider: MarketDataProvider::OnChain,
+            price_usd: None,
+            price_native: Some(sol_reserves / token_reserves),
+            quote_mint: Some(WRAPPED_SOL_MINT.to_string()),
+            liquidity_usd: None,
+            market_cap_usd: None,
+            volume_24h_usd: None,
+            price_change_24h_percent: None,
+        })
+    }
+}
+
+impl TokenDataProvider for OnChainProvider {
+    fn kind(&self) -> MarketDataProvider {
+        MarketDataProvider::OnChain
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        request: &'a MarketDataRequest<'a>,
+    ) -> BoxFuture<'a, Result<Option<MarketData>, Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let pool_snapshot = match (request.pool, request.decimals) {
+                (Some(pool), Some(decimals)) => {
+                    Self::pool_snapshot(pool, request.token_address, decimals)
+                }
+                _ => None,
+            };
+            let snapshot =
+                pool_snapshot.or_else(|| request.bonding_curve.and_then(Self::curve_snapshot));
+
+            Ok(snapshot.map(|snapshot| MarketData {
+                snapshot,
+                token: None,
+                token_call_data: None,
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telegram::Dex;
+
+    const TOKEN_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn pool(quote_mint: &str, base_reserve: u64, quote_reserve: u64) -> Pool {
+        Pool {
+            dex: Dex::RaydiumCpmm,
+            address: String::from("pool"),
+            base_mint: TOKEN_MINT.to_string(),
+            quote_mint: quote_mint.to_string(),
+            base_vault: String::from("base_vault"),
+            quote_vault: String::from("quote_vault"),
+            base_reserve: Some(base_reserve),
+            quote_reserve: Some(quote_reserve),
+            lp_mint: None,
+            open_time: None,
+        }
+    }
+
+    fn bonding_curve(virtual_sol_reserves: u64, virtual_token_reserves: u64) -> BondingCurve {
+        BondingCurve {
+            address: String::from("curve"),
+            virtual_token_reserves,
+            virtual_sol_reserves,
+            real_token_reserves: 0,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            progress_percent: 0.0,
+            graduated: false,
+        }
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("value is set");
+        assert!(
+            (actual - expected).abs() < expected * 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn stable_quoted_pool_is_priced_in_usd() {
+        // 1,000 tokens of 9 decimals against 50 USDC of 6 decimals
+        let pool = pool(USDC_MINT, 1_000 * 10u64.pow(9), 50 * 10u64.pow(6));
+        let snapshot = OnChainProvider::pool_snapshot(&pool, TOKEN_MINT, 9).unwrap();
+
+        assert_close(snapshot.price_native, 0.05);
+        assert_close(snapshot.price_usd, 0.05);
+        assert_close(snapshot.liquidity_usd, 100.0);
+        assert_eq!(snapshot.quote_mint.as_deref(), Some(USDC_MINT));
+    }
+
+    #[test]
+    fn sol_quoted_pool_has_no_usd_price() {
+        // 2,000,000 tokens of 6 decimals against 10 SOL of 9 decimals
+        let pool = pool(
+            WRAPPED_SOL_MINT,
+            2_000_000 * 10u64.pow(6),
+            10 * 10u64.pow(9),
+        );
+        let snapshot = OnChainProvider::pool_snapshot(&pool, TOKEN_MINT, 6).unwrap();
+
+        assert_close(snapshot.price_native, 0.000005);
+        assert_eq!(snapshot.price_usd, None);
+        assert_eq!(snapshot.liquidity_usd, None);
+    }
+
+    #[test]
+    fn pool_of_another_token_or_unknown_quote_is_skipped() {
+        let sol_pool = pool(WRAPPED_SOL_MINT, 1, 1);
+        assert!(OnChainProvider::pool_snapshot(&sol_pool, USDC_MINT, 6).is_none());
+
+        let unknown_quote = pool("unknown", 1, 1);
+        assert!(OnChainProvider::pool_snapshot(&unknown_quote, TOKEN_MINT, 6).is_none());
+
+        let empty = pool(USDC_MINT, 0, 1);
+        assert!(OnChainProvider::pool_snapshot(&empty, TOKEN_MINT, 6).is_none());
+    }
+
+    #[test]
+    fn curve_is_priced_in_sol_from_virtual_reserves() {
+        // Launch reserves: 30 SOL against 1,073,000,000 tokens
+        let curve = bonding_curve(30 * 10u64.pow(9), 1_073_000_000 * 10u64.pow(6));
+        let snapshot = OnChainProvider::curve_snapshot(&curve).unwrap();
+
+        assert_close(snapshot.price_native, 30.0 / 1_073_000_000.0);
+        assert_eq!(snapshot.price_usd, None);
+        assert_eq!(snapshot.quote_mint.as_deref(), Some(WRAPPED_SOL_MINT));
+    }
+
+    #[test]
+    fn graduated_curve_is_skipped() {
+        let mut curve = bonding_curve(1, 1);
+        curve.graduated = true;
+
+        assert!(OnChainProvider::curve_snapshot(&curve).is_none());
+    }
+
+    #[tokio::test]
+    async fn dexscreener_reuses_the_pairs_already_fetched() {
+        let address = "0xa1b2c3d4e5f6a7b8c9d1e2f3a4b5c6d7e8f9a1b2";
+        let pairs: Vec<DexscreenerSearchPair> = serde_json::from_value(serde_json::json!([{
+            "chainId": "base",
+            "pairAddress": "0xpair",
+            "baseToken": { "address": address, "name": "Pepe", "symbol": "PEPE" },
+            "liquidity": { "usd": 250000.0 },
+            "priceUsd": "0.01",
+        }]))
+        .unwrap();
+        let request = MarketDataRequest {
+            token_address: address,
+            chain: Chain::Base,
+            pool: None,
+            bonding_curve: None,
+            decimals: None,
+            token_pairs: Some(&pairs),
+        };
+        // Unroutable, so the test fails if the provider asks Dexscreener again
+        let provider = DexscreenerProvider {
+            reqwest_client: reqwest::Client::builder()
+                .proxy(reqwest::Proxy::all("http://127.0.0.1:9").unwrap())
+                .build()
+                .unwrap(),
+        };
+
+        let market_data = provider.fetch(&request).await.unwrap().unwrap();
+        assert_eq!(market_data.snapshot.price_usd, Some(0.01));
+        assert_eq!(market_data.snapshot.liquidity_usd, Some(250_000.0));
+    }
+}
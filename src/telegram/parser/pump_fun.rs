@@ -0,0 +1,137 @@
+use super::read_u64;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+pub const PUMP_FUN_PROGRAM: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+
+/// Anchor discriminator of the `BondingCurve` account, sha256("account:BondingCurve")[..8]
+const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+
+/// Tokens left to sell on a fresh curve, 793.1M with 6 decimals. Progress is measured
+/// against it, the curve completes once they are all sold.
+const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+
+/// State of a pump.fun bonding curve at call time
+#[derive(Debug, Clone, Serialize)]
+pub struct BondingCurve {
+    pub address: String,
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    /// Lamports raised so far
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    /// Share of the curve sold, 100 once complete
+    pub progress_percent: f64,
+    /// The curve completed and liquidity migrated to a Raydium or PumpSwap pool
+    pub graduated: bool,
+}
+
+impl BondingCurve {
+    /// Decodes the account data, None if it isn't a bonding curve
+    pub fn decode(address: &str, data: &[u8]) -> Option<Self> {
+        if data.get(..8)? != BONDING_CURVE_DISCRIMINATOR {
+            return None;
+        }
+
+        let real_token_reserves = read_u64(data, 24)?;
+        let graduated = *data.get(48)? != 0;
+        let progress_percent = if graduated {
+            100.0
+        } else {
+            let sold = INITIAL_REAL_TOKEN_RESERVES.saturating_sub(real_token_reserves);
+            sold as f64 / INITIAL_REAL_TOKEN_RESERVES as f64 * 100.0
+        };
+
+        Some(Self {
+            address: address.to_string(),
+            virtual_token_reserves: read_u64(data, 8)?,
+            virtual_sol_reserves: read_u64(data, 16)?,
+            real_token_reserves,
+            real_sol_reserves: read_u64(data, 32)?,
+            token_total_supply: read_u64(data, 40)?,
+            progress_percent,
+            graduated,
+        })
+    }
+
+    /// Every pump.fun mint has its curve at the PDA ["bonding-curve", mint]
+    pub fn address_for_mint(mint: &Pubkey) -> Pubkey {
+        let program = Pubkey::from_str(PUMP_FUN_PROGRAM).unwrap();
+        Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &program).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::hash;
+
+    /// Initial virtual reserves of every pump.fun curve
+    const VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+    const VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+    const TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+
+    /// `BondingCurve` account data: the discriminator, five u64 and the `complete` flag,
+    /// followed by the creator key newer curves have
+    fn curve_data(sold: u64, lamports: u64, complete: bool) -> Vec<u8> {
+        let mut data = BONDING_CURVE_DISCRIMINATOR.to_vec();
+        for value in [
+            VIRTUAL_TOKEN_RESERVES - sold,
+            VIRTUAL_SOL_RESERVES + lamports,
+            INITIAL_REAL_TOKEN_RESERVES - sold,
+            lamports,
+            TOTAL_SUPPLY,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(complete as u8);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data
+    }
+
+    #[test]
+    fn discriminator_matches_the_anchor_account_name() {
+        assert_eq!(
+            BONDING_CURVE_DISCRIMINATOR,
+            hash(b"account:BondingCurve").to_bytes()[..8]
+        );
+    }
+
+    #[test]
+    fn decodes_a_curve_being_sold() {
+        let sold = INITIAL_REAL_TOKEN_RESERVES / 4;
+        let curve =
+            BondingCurve::decode("curve", &curve_data(sold, 20_000_000_000, false)).unwrap();
+
+        assert_eq!(curve.virtual_token_reserves, VIRTUAL_TOKEN_RESERVES - sold);
+        assert_eq!(curve.virtual_sol_reserves, 50_000_000_000);
+        assert_eq!(
+            curve.real_token_reserves,
+            INITIAL_REAL_TOKEN_RESERVES - sold
+        );
+        assert_eq!(curve.real_sol_reserves, 20_000_000_000);
+        assert_eq!(curve.token_total_supply, TOTAL_SUPPLY);
+        assert!((curve.progress_percent - 25.0).abs() < 1e-9);
+        assert!(!curve.graduated);
+    }
+
+    #[test]
+    fn a_complete_curve_is_graduated() {
+        let data = curve_data(INITIAL_REAL_TOKEN_RESERVES, 85_000_000_000, true);
+        let curve = BondingCurve::decode("curve", &data).unwrap();
+
+        assert!(curve.graduated);
+        assert_eq!(curve.progress_percent, 100.0);
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let mut data = curve_data(0, 0, false);
+        assert!(BondingCurve::decode("curve", &data[..40]).is_none());
+
+        data[0] ^= 1;
+        assert!(BondingCurve::decode("curve", &data).is_none());
+    }
+}
@@ -0,0 +1,837 @@
+use super::{
+    build_providers, fetch_token_pairs, rank_ticker_matches, BondingCurve, CandidatePosition,
+    CandidateStatus, CandidateValue, Chain, DecodedPool, Denylist, DexscreenerPairsResponse,
+    DexscreenerSearchPair, DexscreenerSearchResponse, MarketDataRequest, Metadata, MintExtensions,
+    OffChainMetadata, Pool, PublicResolver, ScoredCandidate, TickerMention, Token, TokenCall,
+    TokenDataProvider, DEFAULT_MARKET_DATA_PROVIDERS, EVM_CHAINS, PUMP_FUN_PROGRAM,
+    RAYDIUM_AMM_V4_PROGRAM,
+};
+use crate::telegram::{env_or, positive_env_or, secs_env_or, BoundedCache};
+use solana_account_decoder::parse_token::{parse_token, TokenAccountType, UiMint};
+use solana_account_decoder::UiAccountData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::env;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+/// Largest batch `getMultipleAccounts` accepts
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Off-chain metadata is a small JSON, anything bigger isn't worth reading
+const MAX_OFF_CHAIN_METADATA_BYTES: usize = 256 * 1024;
+const OFF_CHAIN_METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_OFF_CHAIN_METADATA_REDIRECTS: usize = 3;
+
+/// An address candidate on its way through the resolver
+struct AddressCandidate {
+    address: String,
+    position: CandidatePosition,
+    chain: Option<Chain>,
+    score: f64,
+}
+
+/// A candidate identified as a token or pair
+struct ResolvedCandidate {
+    status: CandidateStatus,
+    chain: Chain,
+    /// Address of the token the call is for
+    token_address: String,
+    bonding_curve: Option<BondingCurve>,
+    pool: Option<Pool>,
+    /// The token's mint, when the candidate was the mint itself and was just fetched
+    mint: Option<UiMint>,
+    /// Dexscreener pairs of the token, when they were already fetched to resolve it
+    token_pairs: Option<Vec<DexscreenerSearchPair>>,
+}
+
+/// What a Solana address is. Only this is cached, with the static layout of pools:
+/// mints and curve state can change and are read again on every call.
+#[derive(Clone)]
+enum SolanaAccountType {
+    Token,
+    RaydiumPair(DecodedPool),
+    PumpFunCurve,
+    Pool(DecodedPool),
+    Other,
+    /// No account at that address when it was checked. A mint announced before it is
+    /// created shows up later, so this is only trusted for `MISSING_ACCOUNT_TTL_SECS`.
+    Missing {
+        checked_at: Instant,
+    },
+}
+
+/// Looks candidates up on chain and in the market data APIs, and decides which are calls
+pub struct TokenResolver {
+    denylist: Denylist,
+    /// Ticker resolutions below this confidence are kept but rejected
+    min_ticker_confidence: f64,
+    /// Tokens with less liquidity than this are never picked for a ticker
+    min_ticker_liquidity_usd: f64,
+    /// Market data providers in priority order, see `MARKET_DATA_PROVIDERS`
+    providers: Vec<Box<dyn TokenDataProvider>>,
+    reqwest_client: reqwest::Client,
+    /// Fetches creator-chosen metadata uris, with a short timeout and only from public IPs
+    metadata_client: reqwest::Client,
+    solana_client: RpcClient,
+    /// What the most recent Solana addresses turned out to be, so addresses pasted again
+    /// (wallets, popular mints) don't cost another RPC round-trip.
+    /// Only classification is cached, state like mints, curves and reserves is always read fresh.
+    account_cache: Mutex<BoundedCache<String, SolanaAccountType>>,
+    missing_account_ttl: Duration,
+}
+
+impl TokenResolver {
+    pub fn new() -> Self {
+        let min_ticker_confidence = env_or("TICKER_MIN_CONFIDENCE", 0.6);
+        let min_ticker_liquidity_usd = env_or("TICKER_MIN_LIQUIDITY_USD", 10_000.0);
+        let account_cache_capacity = positive_env_or("ACCOUNT_CACHE_CAPACITY", 50_000);
+        // A hung market data request has to time out for the next provider to be tried
+        let reqwest_client = reqwest::Client::builder()
+            .timeout(secs_env_or("HTTP_TIMEOUT_SECS", 10))
+            .build()
+            .expect("HTTP client must build");
+        let metadata_client = reqwest::Client::builder()
+            .timeout(OFF_CHAIN_METADATA_TIMEOUT)
+            .dns_resolver(Arc::new(PublicResolver))
+            .redirect(reqwest::redirect::Policy::custom(|attempt| {
+                if attempt.previous().len() >= MAX_OFF_CHAIN_METADATA_REDIRECTS
+                    || !OffChainMetadata::is_public_url(attempt.url())
+                {
+                    attempt.stop()
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .expect("HTTP client must build");
+        let provider_ids: Vec<String> = env::var("MARKET_DATA_PROVIDERS")
+            .unwrap_or_else(|_| DEFAULT_MARKET_DATA_PROVIDERS.to_string())
+            .split(',')
+            .map(|id| id.trim().to_lowercase())
+            .filter(|id| !id.is_empty())
+            .collect();
+        let providers = build_providers(&provider_ids, &reqwest_client)
+            .expect("MARKET_DATA_PROVIDERS must list birdeye, dexscreener, jupiter or onchain");
+        let commitment = env_or("SOLANA_COMMITMENT", CommitmentConfig::confirmed());
+        let rpc_timeout = secs_env_or("SOLANA_RPC_TIMEOUT_SECS", 30);
+        let solana_client = RpcClient::new_with_timeout_and_commitment(
+            env::var("SOLANA_RPC_URL").unwrap(),
+            rpc_timeout,
+            commitment,
+        );
+
+        Self {
+            denylist: Denylist::new(),
+            min_ticker_confidence,
+            min_ticker_liquidity_usd,
+            providers,
+            reqwest_client,
+            metadata_client,
+            solana_client,
+            account_cache: Mutex::new(BoundedCache::new(account_cache_capacity)),
+            missing_account_ttl: secs_env_or("MISSING_ACCOUNT_TTL_SECS", 60),
+        }
+    }
+
+    /// None for ticker mentions that couldn't be matched to any token
+    pub async fn resolve(
+        &self,
+        candidate: ScoredCandidate,
+        call_timestamp_ms: i64,
+    ) -> Option<TokenCall> {
+        match candidate.value {
+            CandidateValue::Address(address) => Some(
+                self.lookup_candidate(AddressCandidate {
+                    address,
+                    position: candidate.position,
+                    chain: candidate.chain,
+                    score: candidate.score,
+                })
+                .await,
+            ),
+            CandidateValue::Ticker(mention) => {
+                self.resolve_ticker(mention, candidate.position, call_timestamp_ms)
+                    .await
+            }
+        }
+    }
+
+    async fn resolve_ticker(
+        &self,
+        mention: TickerMention,
+        position: CandidatePosition,
+        call_timestamp_ms: i64,
+    ) -> Option<TokenCall> {
+        // 1. Search the symbol and pick the most likely Solana token
+        let pairs = match self.search_pairs(&mention.symbol).await {
+            Ok(pairs) => pairs,
+            Err(e) => {
+                eprintln!(
+                    "Error while searching ticker: {} \nError: {}",
+                    mention.symbol, e
+                );
+                return None;
+            }
+        };
+        let (address, resolution) = match rank_ticker_matches(
+            &mention,
+            &pairs,
+            call_timestamp_ms,
+            self.min_ticker_liquidity_usd,
+        ) {
+            Some(resolved) => resolved,
+            None => {
+                println!("Could not resolve ticker ${}", mention.symbol);
+                return None;
+            }
+        };
+
+        let candidate = AddressCandidate {
+            address,
+            position,
+            chain: Some(Chain::Solana),
+            score: resolution.confidence,
+        };
+
+        // 2. Low confidence matches never count as calls. They are only reported next to
+        // other calls of the same message, a message with nothing else is dropped.
+        let mut token_call = if resolution.confidence < self.min_ticker_confidence {
+            let mut token_call = Self::unresolved_token_call(candidate);
+            token_call.status = CandidateStatus::LowConfidenceTicker;
+            token_call
+        } else {
+            let mut token_call = self.lookup_candidate(candidate).await;
+            if token_call.accepted {
+                token_call.status = CandidateStatus::TickerMatch;
+            }
+            token_call
+        };
+        token_call.ticker = Some(resolution);
+
+        Some(token_call)
+    }
+
+    fn unresolved_token_call(candidate: AddressCandidate) -> TokenCall {
+        TokenCall {
+            address: candidate.address,
+            position: candidate.position,
+            accepted: false,
+            status: CandidateStatus::NotATokenOrPair,
+            detail: None,
+            skip_reason: None,
+            chain: candidate.chain,
+            confidence: candidate.score,
+            ticker: None,
+            bonding_curve: None,
+            pool: None,
+            token: None,
+            market: None,
+            token_call_data: None,
+        }
+    }
+
+    async fn lookup_candidate(&self, candidate: AddressCandidate) -> TokenCall {
+        let chain_hint = candidate.chain;
+        let mut token_call = Self::unresolved_token_call(candidate);
+
+        // 0. Programs, stable mints and configured wallets are pasted all the time,
+        // skip them before spending any request
+        if let Some(reason) = self.denylist.check(&token_call.address) {
+            token_call.status = CandidateStatus::Skipped;
+            token_call.skip_reason = Some(reason);
+            return token_call;
+        }
+
+        // 1. Find out whether the address is a token or a pair, and on which chain.
+        // Market data providers are keyed by token address, so pairs are resolved
+        // to their base token first
+        let resolution = match chain_hint {
+            Some(Chain::Solana) => self.resolve_solana_candidate(&token_call.address).await,
+            _ => {
+                self.resolve_evm_candidate(&token_call.address, chain_hint)
+                    .await
+            }
+        };
+
+        let ResolvedCandidate {
+            status,
+            chain,
+            token_address,
+            bonding_curve,
+            pool,
+            mint,
+            token_pairs,
+        } = match resolution {
+            Ok(Some(resolved)) => resolved,
+            Ok(None) => return token_call,
+            Err((status, err)) => {
+                token_call.status = status;
+                token_call.detail = Some(err);
+                return token_call;
+            }
+        };
+        token_call.chain = Some(chain);
+        token_call.bonding_curve = bonding_curve;
+        token_call.pool = pool;
+
+        // 2. Token-2022 extensions are read from the mint account, and its decimals
+        // let the on-chain provider price pool reserves
+        let mint = match (chain, mint) {
+            (Chain::Solana, Some(mint)) => Some(mint),
+            (Chain::Solana, None) => self.fetch_mint(&token_address).await,
+            _ => None,
+        };
+        let token_2022 = mint.as_ref().and_then(MintExtensions::from_mint);
+
+        // 3. Ask the providers in priority order until one has the token
+        let request = MarketDataRequest {
+            token_address: &token_address,
+            chain,
+            pool: token_call.pool.as_ref(),
+            bonding_curve: token_call.bonding_curve.as_ref(),
+            decimals: mint.as_ref().map(|mint| mint.decimals),
+            token_pairs: token_pairs.as_deref(),
+        };
+        let mut market_data = None;
+        let mut errors = Vec::new();
+        for provider in &self.providers {
+            match provider.fetch(&request).await {
+                Ok(Some(data)) => {
+                    market_data = Some(data);
+                    break;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    eprintln!(
+                        "Error while fetching market data from {}: {} \nError: {}",
+                        provider.kind().id(),
+                        token_address,
+                        err
+                    );
+                    errors.push(format!("{}: {}", provider.kind().id(), err));
+                }
+            }
+        }
+
+        // 4. Providers without token details (Jupiter, on-chain) and brand-new Solana
+        // tokens nobody has indexed yet use the on-chain metadata
+        let (market, provider_token, token_call_data) = match market_data {
+            Some(data) => (Some(data.snapshot), data.token, data.token_call_data),
+            None => (None, None, None),
+        };
+        let token = match (provider_token, &mint) {
+            (Some(token), _) => Some(token),
+            (None, Some(mint)) => {
+                self.fetch_onchain_token(&token_address, mint, token_2022.as_ref())
+                    .await
+            }
+            (None, None) => None,
+        };
+        let mut token = match token {
+            Some(token) => token,
+            None if !errors.is_empty() => {
+                token_call.status = CandidateStatus::MarketDataError;
+                token_call.detail = Some(errors.join("; "));
+                return token_call;
+            }
+            None => {
+                token_call.status = CandidateStatus::NoMarketData;
+                return token_call;
+            }
+        };
+        token.market_data_pending = market.is_none();
+        token.risk_flags = token_2022
+            .as_ref()
+            .map(MintExtensions::risk_flags)
+            .unwrap_or_default();
+        token.token_2022 = token_2022;
+
+        token_call.accepted = status.is_accepted();
+        token_call.status = status;
+        token_call.token = Some(token);
+        token_call.market = market;
+        token_call.token_call_data = token_call_data;
+
+        token_call
+    }
+
+    /// Token built from its Metaplex metadata, or the metadata stored in a Token-2022 mint.
+    /// None if the mint has neither
+    async fn fetch_onchain_token(
+        &self,
+        mint_address: &str,
+        mint: &UiMint,
+        token_2022: Option<&MintExtensions>,
+    ) -> Option<Token> {
+        // 1. Metaplex metadata account of the mint
+        let metadata_address = Metadata::address_for_mint(&Pubkey::from_str(mint_address).ok()?);
+        let metadata = match self.solana_client.get_account(&metadata_address).await {
+            Ok(account) => Metadata::decode(&account.data),
+            Err(_) => None,
+        };
+
+        // 2. Fall back to the Token-2022 metadata extension
+        let (name, symbol, uri, update_authority) = match metadata {
+            Some(metadata) => (
+                metadata.name,
+                metadata.symbol,
+                metadata.uri,
+                Some(metadata.update_authority),
+            ),
+            None => {
+                let metadata = token_2022?.metadata.clone()?;
+                (metadata.name, metadata.symbol, metadata.uri, None)
+            }
+        };
+
+        // 3. The off-chain JSON has the image and socials
+        let off_chain = match self.fetch_offchain_metadata(&uri).await {
+            Ok(off_chain) => off_chain,
+            Err(err) => {
+                eprintln!(
+                    "Error while fetching off-chain metadata of {} : {} \nError: {}",
+                    mint_address, uri, err
+                );
+                OffChainMetadata::default()
+            }
+        };
+
+        Some(Token {
+            address: mint_address.to_string(),
+            decimals: Some(mint.decimals),
+            name,
+            symbol,
+            network: Chain::Solana.id().to_string(),
+            logo_uri: off_chain.image.clone(),
+            extensions: off_chain.socials(),
+            token_2022: None,
+            risk_flags: Vec::new(),
+            update_authority,
+            market_data_pending: true,
+        })
+    }
+
+    async fn fetch_offchain_metadata(
+        &self,
+        uri: &str,
+    ) -> Result<OffChainMetadata, Box<dyn std::error::Error>> {
+        if uri.trim().is_empty() {
+            return Ok(OffChainMetadata::default());
+        }
+        let url = OffChainMetadata::url(uri).ok_or("not a public http(s), ipfs or ar uri")?;
+
+        // 1. Bodies announcing more than the limit are rejected before reading them
+        let mut response = self
+            .metadata_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_OFF_CHAIN_METADATA_BYTES as u64)
+        {
+            return Err("off-chain metadata is too large".into());
+        }
+
+        // 2. The length can be missing or wrong, so the limit is checked while reading too
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_OFF_CHAIN_METADATA_BYTES {
+                return Err("off-chain metadata is too large".into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn resolve_solana_candidate(
+        &self,
+        address: &str,
+    ) -> Result<Option<ResolvedCandidate>, (CandidateStatus, String)> {
+        let resolved = |status, token_address: String| ResolvedCandidate {
+            status,
+            chain: Chain::Solana,
+            token_address,
+            bonding_curve: None,
+            pool: None,
+            mint: None,
+            token_pairs: None,
+        };
+
+        let (account_type, account) = self.determine_account_type(address).await?;
+        match account_type {
+            // A mint fetched just now is reused, a cached one is read again later
+            SolanaAccountType::Token => Ok(Some(ResolvedCandidate {
+                bonding_curve: self.fetch_bonding_curve_for_mint(address).await,
+                mint: account.and_then(|account| match parse_token(&account.data, None) {
+                    Ok(TokenAccountType::Mint(mint)) => Some(mint),
+                    _ => None,
+                }),
+                ..resolved(CandidateStatus::TokenMint, address.to_string())
+            })),
+            // The curve is read again to record its state at call time
+            SolanaAccountType::PumpFunCurve => match self.fetch_curve_mint(address).await {
+                Some(mint) => Ok(Some(ResolvedCandidate {
+                    bonding_curve: self.fetch_bonding_curve_for_mint(&mint).await,
+                    ..resolved(CandidateStatus::PumpFunCurve, mint)
+                })),
+                None => Err((
+                    CandidateStatus::AccountUnavailable,
+                    String::from("could not find the mint held by the bonding curve"),
+                )),
+            },
+            SolanaAccountType::RaydiumPair(decoded_pool) => {
+                let pool = decoded_pool.load(&self.solana_client).await;
+                Ok(Some(ResolvedCandidate {
+                    pool: Some(pool.clone()),
+                    ..resolved(CandidateStatus::RaydiumPair, pool.base_mint)
+                }))
+            }
+            SolanaAccountType::Pool(decoded_pool) => {
+                let pool = decoded_pool.load(&self.solana_client).await;
+                Ok(Some(ResolvedCandidate {
+                    pool: Some(pool.clone()),
+                    ..resolved(CandidateStatus::Pool, pool.base_mint)
+                }))
+            }
+            SolanaAccountType::Other => Ok(None),
+            SolanaAccountType::Missing { .. } => Err((
+                CandidateStatus::AccountUnavailable,
+                String::from("no account at this address"),
+            )),
+        }
+    }
+
+    /// Curve state of a pump.fun mint, None for tokens launched elsewhere
+    async fn fetch_bonding_curve_for_mint(&self, mint: &str) -> Option<BondingCurve> {
+        let curve_address = BondingCurve::address_for_mint(&Pubkey::from_str(mint).ok()?);
+        let account = self.solana_client.get_account(&curve_address).await.ok()?;
+
+        BondingCurve::decode(&curve_address.to_string(), &account.data)
+    }
+
+    /// Curves don't store their mint, but they hold the unsold supply in a token account
+    async fn fetch_curve_mint(&self, curve_address: &str) -> Option<String> {
+        let owner = Pubkey::from_str(curve_address).ok()?;
+
+        for token_program in [TOKEN_PROGRAM, TOKEN_2022_PROGRAM] {
+            let filter = TokenAccountsFilter::ProgramId(Pubkey::from_str(token_program).ok()?);
+            let token_accounts = match self
+                .solana_client
+                .get_token_accounts_by_owner(&owner, filter)
+                .await
+            {
+                Ok(token_accounts) => token_accounts,
+                Err(e) => {
+                    eprintln!(
+                        "Error while fetching token accounts of bonding curve: {} \nError: {}",
+                        curve_address, e
+                    );
+                    continue;
+                }
+            };
+
+            let mint = token_accounts.into_iter().find_map(|token_account| {
+                match token_account.account.data {
+                    UiAccountData::Json(parsed) => {
+                        parsed.parsed["info"]["mint"].as_str().map(str::to_string)
+                    }
+                    _ => None,
+                }
+            });
+            if mint.is_some() {
+                return mint;
+            }
+        }
+
+        None
+    }
+
+    /// 0x addresses can be on any EVM chain, so Dexscreener is asked which chains have it.
+    /// The inferred chain wins when the token exists there, otherwise the most liquid one.
+    async fn resolve_evm_candidate(
+        &self,
+        address: &str,
+        chain_hint: Option<Chain>,
+    ) -> Result<Option<ResolvedCandidate>, (CandidateStatus, String)> {
+        // 1. Token contract: pairs where the address is the base token
+        let pairs = fetch_token_pairs(&self.reqwest_client, address)
+            .await
+            .map_err(|err| (CandidateStatus::MarketDataError, err.to_string()))?;
+
+        let token_pairs: Vec<(Chain, &DexscreenerSearchPair)> = pairs
+            .iter()
+            .filter(|pair| pair.base_token.address.eq_ignore_ascii_case(address))
+            .filter_map(|pair| Some((Chain::from_id(&pair.chain_id)?, pair)))
+            .filter(|(chain, _)| EVM_CHAINS.contains(chain))
+            .collect();
+
+        let hinted_pair = token_pairs
+            .iter()
+            .find(|(chain, _)| Some(*chain) == chain_hint);
+        let most_liquid_pair = token_pairs.iter().max_by(|a, b| {
+            let liquidity = |pair: &DexscreenerSearchPair| {
+                pair.liquidity
+                    .as_ref()
+                    .and_then(|liquidity| liquidity.usd)
+                    .unwrap_or(0.0)
+            };
+            liquidity(a.1).total_cmp(&liquidity(b.1))
+        });
+
+        let token = hinted_pair
+            .or(most_liquid_pair)
+            .map(|(chain, pair)| (*chain, pair.base_token.address.clone()));
+        if let Some((chain, token_address)) = token {
+            // The market data of the token is in the pairs just fetched
+            return Ok(Some(ResolvedCandidate {
+                status: CandidateStatus::TokenContract,
+                chain,
+                token_address,
+                bonding_curve: None,
+                pool: None,
+                mint: None,
+                token_pairs: Some(pairs),
+            }));
+        }
+
+        // 2. Pair address: try the inferred chain first, then the others
+        let chains = chain_hint.into_iter().chain(
+            EVM_CHAINS
+                .into_iter()
+                .filter(|chain| Some(*chain) != chain_hint),
+        );
+        for chain in chains {
+            match self.fetch_pair_base_token(address, chain).await {
+                Ok(Some(base_token)) => {
+                    return Ok(Some(ResolvedCandidate {
+                        status: CandidateStatus::EvmPair,
+                        chain,
+                        token_address: base_token,
+                        bonding_curve: None,
+                        pool: None,
+                        mint: None,
+                        token_pairs: None,
+                    }))
+                }
+                Ok(None) => continue,
+                Err(e) => eprintln!(
+                    "Error while fetching {} pair: {} \nError: {}",
+                    chain.id(),
+                    address,
+                    e
+                ),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The account is returned too when it was fetched instead of classified from the cache
+    async fn determine_account_type(
+        &self,
+        address: &str,
+    ) -> Result<(SolanaAccountType, Option<Account>), (CandidateStatus, String)> {
+        // 1. Form the solana public key for the inputted address
+        let pub_key = Pubkey::from_str(address)
+            .map_err(|e| (CandidateStatus::InvalidAddress, e.to_string()))?;
+
+        // 2. Addresses classified before, or prefetched with the rest of the batch
+        if let Some(account_type) = self.cached_account_type(address) {
+            return Ok((account_type, None));
+        }
+
+        // 3. Fetch solana account info associated with inputted address
+        let account = match self.solana_client.get_account(&pub_key).await {
+            Ok(account) => account,
+            Err(e) => {
+                eprintln!(
+                    "Error while trying to get_account for solana address: {} \nError: {}",
+                    address, e
+                );
+                return Err((CandidateStatus::AccountUnavailable, e.to_string()));
+            }
+        };
+
+        let account_type = Self::classify_account(address, &account);
+        self.account_cache
+            .lock()
+            .unwrap()
+            .insert(address.to_string(), account_type.clone());
+
+        Ok((account_type, Some(account)))
+    }
+
+    /// Fetches the uncached Solana candidates with `getMultipleAccounts`, 100 per request,
+    /// and caches what they are, including for a while that an account doesn't exist.
+    pub async fn prefetch(&self, candidates: &[ScoredCandidate]) {
+        // 1. Only Solana addresses not classified yet, each once
+        let mut addresses: Vec<&str> = candidates
+            .iter()
+            .filter(|candidate| candidate.chain == Some(Chain::Solana))
+            .filter_map(|candidate| match &candidate.value {
+                CandidateValue::Address(address) => Some(address.as_str()),
+                CandidateValue::Ticker(_) => None,
+            })
+            .filter(|address| {
+                self.denylist.check(address).is_none()
+                    && self.cached_account_type(address).is_none()
+            })
+            .collect();
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        let pub_keys: Vec<(&str, Pubkey)> = addresses
+            .into_iter()
+            .filter_map(|address| Some((address, Pubkey::from_str(address).ok()?)))
+            .collect();
+
+        // 2. Fetch and classify them in chunks
+        for chunk in pub_keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys: Vec<Pubkey> = chunk.iter().map(|(_, pub_key)| *pub_key).collect();
+            let accounts = match self.solana_client.get_multiple_accounts(&keys).await {
+                Ok(accounts) => accounts,
+                Err(e) => {
+                    eprintln!(
+                        "Error while trying to get_multiple_accounts for {} addresses \nError: {}",
+                        keys.len(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            for ((address, _), account) in chunk.iter().zip(accounts) {
+                let account_type = match account {
+                    Some(account) => Self::classify_account(address, &account),
+                    None => SolanaAccountType::Missing {
+                        checked_at: Instant::now(),
+                    },
+                };
+                self.account_cache
+                    .lock()
+                    .unwrap()
+                    .insert(address.to_string(), account_type);
+            }
+        }
+    }
+
+    /// What the address was classified as, unless it was missing too long ago to still trust
+    fn cached_account_type(&self, address: &str) -> Option<SolanaAccountType> {
+        let mut account_cache = self.account_cache.lock().unwrap();
+        match account_cache.get(address)? {
+            SolanaAccountType::Missing { checked_at }
+                if checked_at.elapsed() >= self.missing_account_ttl =>
+            {
+                account_cache.remove(address);
+                None
+            }
+            account_type => Some(account_type.clone()),
+        }
+    }
+
+    fn classify_account(address: &str, account: &Account) -> SolanaAccountType {
+        // 1. Raydium pairs always have the same owner of Raydium Liq Pool V4: 675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8
+        // Their AmmInfo already names both mints, so no API call is needed to find the base token
+        let owner = account.owner.to_string();
+        if owner == RAYDIUM_AMM_V4_PROGRAM {
+            return DecodedPool::decode(&owner, address, &account.data)
+                .map_or(SolanaAccountType::Other, SolanaAccountType::RaydiumPair);
+        }
+
+        // 2. Pump.fun bonding curves, for calls linking the curve instead of the mint
+        if owner == PUMP_FUN_PROGRAM {
+            return BondingCurve::decode(address, &account.data)
+                .map_or(SolanaAccountType::Other, |_| {
+                    SolanaAccountType::PumpFunCurve
+                });
+        }
+
+        // 3. Pools of the other AMMs are decoded locally, no API round-trip needed
+        if let Some(pool) = DecodedPool::decode(&owner, address, &account.data) {
+            return SolanaAccountType::Pool(pool);
+        }
+
+        // 4. Attempt to fetch token info. If it's not a token it will throw error
+        match parse_token(&account.data, None) {
+            Ok(TokenAccountType::Mint(_)) => SolanaAccountType::Token,
+            _ => SolanaAccountType::Other,
+        }
+    }
+
+    /// Current state of a mint, read on every call since authorities and extensions can change
+    async fn fetch_mint(&self, mint_address: &str) -> Option<UiMint> {
+        let account = self
+            .solana_client
+            .get_account(&Pubkey::from_str(mint_address).ok()?)
+            .await
+            .ok()?;
+
+        match parse_token(&account.data, None) {
+            Ok(TokenAccountType::Mint(mint)) => Some(mint),
+            _ => None,
+        }
+    }
+
+    /// Base token address of a pair, None if Dexscreener doesn't know the pair on that chain
+    async fn fetch_pair_base_token(
+        &self,
+        pair_address: &str,
+        chain: Chain,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        // Construct the URL to fetch the pair details
+        let dexscreener_pairs_query = format!(
+            "https://api.dexscreener.com/latest/dex/pairs/{}/{}",
+            chain.id(),
+            pair_address
+        );
+
+        // Make the HTTP request to get the pair
+        let dexscreener_pair_response: DexscreenerPairsResponse = self
+            .reqwest_client
+            .get(&dexscreener_pairs_query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(dexscreener_pair_response
+            .pair
+            .map(|pair| pair.base_token.address))
+    }
+
+    async fn search_pairs(
+        &self,
+        query: &str,
+    ) -> Result<Vec<DexscreenerSearchPair>, Box<dyn std::error::Error>> {
+        let dexscreener_search_query =
+            format!("https://api.dexscreener.com/latest/dex/search?q={}", query);
+
+        let dexscreener_search_response: DexscreenerSearchResponse = self
+            .reqwest_client
+            .get(&dexscreener_search_query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(dexscreener_search_response.pairs.unwrap_or_default())
+    }
+}
+
+impl Default for TokenResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,276 @@
+use super::DexscreenerSearchPair;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Words that turn a nearby bare uppercase word into a ticker mention, e.g. "aping BONK"
+const CALL_KEYWORDS: [&str; 20] = [
+    "buy", "buying", "bought", "ape", "aping", "aped", "send", "sending", "sends", "call",
+    "calling", "entry", "long", "longing", "gem", "moon", "pump", "loading", "loaded", "bullish",
+];
+
+/// Words and cashtags that show up in calls without being the called token,
+/// e.g. "$SOL" in "bought with 2 $SOL"
+const NOT_TICKERS: [&str; 20] = [
+    "CA", "LP", "MC", "MCAP", "ATH", "DEX", "NFA", "DYOR", "TG", "CT", "USD", "USDC", "USDT",
+    "SOL", "ETH", "BTC", "NOW", "NEW", "GM", "ALPHA",
+];
+
+/// How many words away from a call keyword a bare symbol may be
+const KEYWORD_DISTANCE: usize = 2;
+
+/// Weight of a pair halves every this many days between its creation and the call
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+const MIN_RECENCY_WEIGHT: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TickerMentionKind {
+    /// `$BONK`
+    Cashtag,
+    /// Bare uppercase symbol next to a call keyword, e.g. "BONK sending"
+    Keyword,
+}
+
+impl TickerMentionKind {
+    /// Bare symbols are more often plain words, so they can't reach full confidence
+    pub fn weight(&self) -> f64 {
+        match self {
+            Self::Cashtag => 1.0,
+            Self::Keyword => 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickerMention {
+    pub symbol: String,
+    pub kind: TickerMentionKind,
+    /// Byte offsets into the message text
+    pub start: usize,
+    pub end: usize,
+}
+
+/// How a ticker mention was resolved to a mint
+#[derive(Debug, Clone, Serialize)]
+pub struct TickerResolution {
+    pub symbol: String,
+    pub mention: TickerMentionKind,
+    /// Share of the liquidity and recency weight held by the chosen token, scaled by
+    /// how reliable the mention is. Explicit address calls don't have one.
+    pub confidence: f64,
+    /// Distinct tokens found with the same symbol
+    pub competing_tokens: usize,
+    pub liquidity_usd: f64,
+}
+
+pub struct TickerExtractor {
+    cashtag_regex: Regex,
+    word_regex: Regex,
+}
+
+impl TickerExtractor {
+    pub fn new() -> Self {
+        Self {
+            cashtag_regex: Regex::new(r"\$([A-Za-z][A-Za-z0-9]{1,9})\b").unwrap(),
+            word_regex: Regex::new(r"[A-Za-z0-9]+").unwrap(),
+        }
+    }
+
+    /// Cashtags first, then bare symbols near call keywords, each symbol once
+    pub fn extract(&self, text: &str) -> Vec<TickerMention> {
+        let mut mentions: Vec<TickerMention> = Vec::new();
+
+        for captures in self.cashtag_regex.captures_iter(text) {
+            let mat = captures.get(0).unwrap();
+            let symbol = captures[1].to_uppercase();
+            if NOT_TICKERS.contains(&symbol.as_str()) {
+                continue;
+            }
+            Self::push_mention(
+                &mut mentions,
+                TickerMention {
+                    symbol,
+                    kind: TickerMentionKind::Cashtag,
+                    start: mat.start(),
+                    end: mat.end(),
+                },
+            );
+        }
+
+        let words: Vec<regex::Match> = self.word_regex.find_iter(text).collect();
+        for (index, word) in words.iter().enumerate() {
+            if !Self::is_bare_symbol(word.as_str()) {
+                continue;
+            }
+
+            let near_keyword = words[index.saturating_sub(KEYWORD_DISTANCE)
+                ..(index + KEYWORD_DISTANCE + 1).min(words.len())]
+                .iter()
+                .any(|other| CALL_KEYWORDS.contains(&other.as_str().to_lowercase().as_str()));
+            if near_keyword {
+                Self::push_mention(
+                    &mut mentions,
+                    TickerMention {
+                        symbol: word.as_str().to_string(),
+                        kind: TickerMentionKind::Keyword,
+                        start: word.start(),
+                        end: word.end(),
+                    },
+                );
+            }
+        }
+
+        mentions
+    }
+
+    fn is_bare_symbol(word: &str) -> bool {
+        (2..=10).contains(&word.len())
+            && word.starts_with(|c: char| c.is_ascii_uppercase())
+            && word
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && !NOT_TICKERS.contains(&word)
+            && !CALL_KEYWORDS.contains(&word.to_lowercase().as_str())
+    }
+
+    fn push_mention(mentions: &mut Vec<TickerMention>, mention: TickerMention) {
+        if mentions.iter().all(|other| other.symbol != mention.symbol) {
+            mentions.push(mention);
+        }
+    }
+}
+
+impl Default for TickerExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Picks the Solana token most likely meant by a ticker mention among search results.
+/// Each token is weighted by the liquidity of its pairs, discounted by how long before
+/// the call the pair was created. Pairs created after the call are ignored, and so are
+/// tokens with less than `min_liquidity_usd` in total.
+/// Returns the mint address and how it was resolved.
+pub fn rank_ticker_matches(
+    mention: &TickerMention,
+    pairs: &[DexscreenerSearchPair],
+    call_timestamp_ms: i64,
+    min_liquidity_usd: f64,
+) -> Option<(String, TickerResolution)> {
+    // 1. Sum the weights of every matching pair per base token
+    let mut weights: HashMap<&str, (f64, f64)> = HashMap::new();
+    for pair in pairs {
+        if pair.chain_id != "solana"
+            || !pair.base_token.symbol.eq_ignore_ascii_case(&mention.symbol)
+        {
+            continue;
+        }
+
+        let created_at = pair.pair_created_at.unwrap_or(call_timestamp_ms);
+        if created_at > call_timestamp_ms {
+            continue;
+        }
+
+        let liquidity = pair
+            .liquidity
+            .as_ref()
+            .and_then(|liquidity| liquidity.usd)
+            .unwrap_or(0.0);
+        let age_days = (call_timestamp_ms - created_at) as f64 / 86_400_000.0;
+        let recency = 0.5_f64
+            .powf(age_days / RECENCY_HALF_LIFE_DAYS)
+            .max(MIN_RECENCY_WEIGHT);
+
+        let entry = weights.entry(&pair.base_token.address).or_default();
+        entry.0 += liquidity * recency;
+        entry.1 += liquidity;
+    }
+
+    // 2. Barely traded tokens (copycats, dead launches) can't be the one meant,
+    // however large their share of the rest
+    weights.retain(|_, (_, liquidity)| *liquidity >= min_liquidity_usd);
+
+    // 3. The heaviest token wins, its share of the total weight is the confidence
+    let total_weight: f64 = weights.values().map(|(weight, _)| weight).sum();
+    let (address, (weight, liquidity_usd)) =
+        weights.iter().max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))?;
+
+    let share = if total_weight > 0.0 {
+        weight / total_weight
+    } else {
+        1.0 / weights.len() as f64
+    };
+
+    Some((
+        address.to_string(),
+        TickerResolution {
+            symbol: mention.symbol.clone(),
+            mention: mention.kind,
+            confidence: share * mention.kind.weight(),
+            competing_tokens: weights.len(),
+            liquidity_usd: *liquidity_usd,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALL_TIMESTAMP_MS: i64 = 1_700_000_000_000;
+
+    fn mention(symbol: &str) -> TickerMention {
+        TickerMention {
+            symbol: symbol.to_string(),
+            kind: TickerMentionKind::Cashtag,
+            start: 0,
+            end: symbol.len() + 1,
+        }
+    }
+
+    fn pair(address: &str, symbol: &str, liquidity_usd: f64) -> DexscreenerSearchPair {
+        serde_json::from_value(serde_json::json!({
+            "chainId": "solana",
+            "pairAddress": format!("{}-pair", address),
+            "baseToken": { "address": address, "name": symbol, "symbol": symbol },
+            "liquidity": { "usd": liquidity_usd },
+            "pairCreatedAt": CALL_TIMESTAMP_MS - 86_400_000,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn picks_the_most_liquid_token() {
+        let pairs = [
+            pair("big", "BONK", 900_000.0),
+            pair("small", "BONK", 100_000.0),
+        ];
+        let (address, resolution) =
+            rank_ticker_matches(&mention("BONK"), &pairs, CALL_TIMESTAMP_MS, 10_000.0).unwrap();
+
+        assert_eq!(address, "big");
+        assert_eq!(resolution.competing_tokens, 2);
+        assert!((resolution.confidence - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_lone_illiquid_token_is_not_a_match() {
+        let pairs = [pair("dust", "BONK", 50.0)];
+
+        assert!(
+            rank_ticker_matches(&mention("BONK"), &pairs, CALL_TIMESTAMP_MS, 10_000.0).is_none()
+        );
+    }
+
+    #[test]
+    fn illiquid_tokens_do_not_dilute_the_confidence() {
+        let pairs = [pair("real", "BONK", 500_000.0), pair("dust", "BONK", 50.0)];
+        let (address, resolution) =
+            rank_ticker_matches(&mention("BONK"), &pairs, CALL_TIMESTAMP_MS, 10_000.0).unwrap();
+
+        assert_eq!(address, "real");
+        assert_eq!(resolution.competing_tokens, 1);
+        assert!((resolution.confidence - 1.0).abs() < 1e-9);
+    }
+}
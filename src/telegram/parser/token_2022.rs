@@ -0,0 +1,327 @@
+use serde::Serialize;
+use solana_account_decoder::parse_token::{UiAccountState, UiMint};
+use solana_account_decoder::parse_token_extension::{UiExtension, UiTransferFee};
+
+/// Transfer fees above this many basis points (1%) are flagged
+const HIGH_TRANSFER_FEE_BASIS_POINTS: u16 = 100;
+
+/// Token-2022 extensions of a mint that matter when judging a call
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MintExtensions {
+    /// Fee applied to transfers before `newer_transfer_fee.epoch`
+    pub older_transfer_fee: Option<TransferFee>,
+    /// Fee applied from its epoch on. Only scheduled while that epoch is still ahead,
+    /// the authority can raise it for the epochs after next.
+    pub newer_transfer_fee: Option<TransferFee>,
+    pub transfer_fee_authority: Option<String>,
+    /// Can transfer or burn tokens out of any holder's account
+    pub permanent_delegate: Option<String>,
+    pub non_transferable: bool,
+    /// Program invoked on every transfer, can block sells
+    pub transfer_hook_program: Option<String>,
+    pub metadata_address: Option<String>,
+    pub mint_close_authority: Option<String>,
+    /// New token accounts start frozen
+    pub default_frozen: bool,
+    pub interest_bearing: bool,
+    /// Name, symbol and uri stored in the mint itself
+    pub metadata: Option<MintMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferFee {
+    /// First epoch the fee applies to
+    pub epoch: u64,
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl From<&UiTransferFee> for TransferFee {
+    fn from(fee: &UiTransferFee) -> Self {
+        Self {
+            epoch: fee.epoch,
+            basis_points: fee.transfer_fee_basis_points,
+            maximum_fee: fee.maximum_fee,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MintMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Extension setups that let the creator take or lock holders' tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskFlag {
+    PermanentDelegate,
+    TransferHook,
+    HighTransferFee,
+    NonTransferable,
+    DefaultFrozen,
+    MintCloseAuthority,
+}
+
+impl MintExtensions {
+    /// None for mints without extensions, which is every classic SPL token
+    pub fn from_mint(mint: &UiMint) -> Option<Self> {
+        if mint.extensions.is_empty() {
+            return None;
+        }
+
+        let mut extensions = Self::default();
+        for extension in &mint.extensions {
+            match extension {
+                UiExtension::TransferFeeConfig(config) => {
+                    extensions.older_transfer_fee = Some((&config.older_transfer_fee).into());
+                    extensions.newer_transfer_fee = Some((&config.newer_transfer_fee).into());
+                    extensions.transfer_fee_authority =
+                        config.transfer_fee_config_authority.clone();
+                }
+                UiExtension::PermanentDelegate(delegate) => {
+                    extensions.permanent_delegate = delegate.delegate.clone();
+                }
+                UiExtension::NonTransferable => extensions.non_transferable = true,
+                UiExtension::TransferHook(hook) => {
+                    extensions.transfer_hook_program = hook.program_id.clone();
+                }
+                UiExtension::MetadataPointer(pointer) => {
+                    extensions.metadata_address = pointer.metadata_address.clone();
+                }
+                UiExtension::MintCloseAuthority(authority) => {
+                    extensions.mint_close_authority = authority.close_authority.clone();
+                }
+                UiExtension::DefaultAccountState(state) => {
+                    extensions.default_frozen =
+                        matches!(state.account_state, UiAccountState::Frozen);
+                }
+                UiExtension::InterestBearingConfig(_) => extensions.interest_bearing = true,
+                UiExtension::TokenMetadata(metadata) => {
+                    extensions.metadata = Some(MintMetadata {
+                        name: metadata.name.clone(),
+                        symbol: metadata.symbol.clone(),
+                        uri: metadata.uri.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Some(extensions)
+    }
+
+    pub fn risk_flags(&self) -> Vec<RiskFlag> {
+        let mut risk_flags = Vec::new();
+
+        if self.permanent_delegate.is_some() {
+            risk_flags.push(RiskFlag::PermanentDelegate);
+        }
+        if self.transfer_hook_program.is_some() {
+            risk_flags.push(RiskFlag::TransferHook);
+        }
+        // Flagged whether the high fee applies now or is scheduled
+        if [&self.older_transfer_fee, &self.newer_transfer_fee]
+            .into_iter()
+            .flatten()
+            .any(|fee| fee.basis_points > HIGH_TRANSFER_FEE_BASIS_POINTS)
+        {
+            risk_flags.push(RiskFlag::HighTransferFee);
+        }
+        if self.non_transferable {
+            risk_flags.push(RiskFlag::NonTransferable);
+        }
+        if self.default_frozen {
+            risk_flags.push(RiskFlag::DefaultFrozen);
+        }
+        if self.mint_close_authority.is_some() {
+            risk_flags.push(RiskFlag::MintCloseAuthority);
+        }
+
+        risk_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_account_decoder::parse_token::{parse_token, TokenAccountType};
+    use solana_sdk::program_option::COption;
+    use solana_sdk::pubkey::Pubkey;
+    use spl_pod::optional_keys::OptionalNonZeroPubkey;
+    use spl_token_2022::extension::default_account_state::DefaultAccountState;
+    use spl_token_2022::extension::metadata_pointer::MetadataPointer;
+    use spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+    use spl_token_2022::extension::non_transferable::NonTransferable;
+    use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+    use spl_token_2022::extension::transfer_fee::{self, TransferFeeConfig};
+    use spl_token_2022::extension::transfer_hook::TransferHook;
+    use spl_token_2022::extension::{ExtensionType, StateWithExtensionsMut};
+    use spl_token_2022::state::{AccountState, Mint};
+    use spl_token_metadata_interface::state::TokenMetadata;
+
+    /// Mint account data built with the Token-2022 program's own state types,
+    /// then read back the way the resolver reads fetched mints
+    fn mint(
+        extension_types: &[ExtensionType],
+        extra_len: usize,
+        init: impl FnOnce(&mut StateWithExtensionsMut<Mint>),
+    ) -> UiMint {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(extension_types).unwrap();
+        let mut data = vec![0; len + extra_len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        init(&mut state);
+        state.base = Mint {
+            mint_authority: COption::None,
+            supply: 1_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        match parse_token(&data, None).unwrap() {
+            TokenAccountType::Mint(mint) => mint,
+            _ => panic!("not parsed as a mint"),
+        }
+    }
+
+    fn key(pubkey: Pubkey) -> OptionalNonZeroPubkey {
+        OptionalNonZeroPubkey::try_from(Some(pubkey)).unwrap()
+    }
+
+    fn fee(epoch: u64, basis_points: u16, maximum_fee: u64) -> transfer_fee::TransferFee {
+        transfer_fee::TransferFee {
+            epoch: epoch.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        }
+    }
+
+    #[test]
+    fn classic_mints_have_no_extensions() {
+        let mint = mint(&[], 0, |_| {});
+
+        assert!(MintExtensions::from_mint(&mint).is_none());
+    }
+
+    #[test]
+    fn reads_both_transfer_fees() {
+        let authority = Pubkey::new_unique();
+        let mint = mint(&[ExtensionType::TransferFeeConfig], 0, |state| {
+            let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+            config.transfer_fee_config_authority = key(authority);
+            config.older_transfer_fee = fee(500, 50, 1_000);
+            config.newer_transfer_fee = fee(600, 1_000, u64::MAX);
+        });
+        let extensions = MintExtensions::from_mint(&mint).unwrap();
+
+        let older = extensions.older_transfer_fee.as_ref().unwrap();
+        assert_eq!(
+            (older.epoch, older.basis_points, older.maximum_fee),
+            (500, 50, 1_000)
+        );
+        let newer = extensions.newer_transfer_fee.as_ref().unwrap();
+        assert_eq!((newer.epoch, newer.basis_points), (600, 1_000));
+        assert_eq!(
+            extensions.transfer_fee_authority,
+            Some(authority.to_string())
+        );
+        // The scheduled fee is above 1%, so the mint is flagged already
+        assert_eq!(extensions.risk_flags(), vec![RiskFlag::HighTransferFee]);
+    }
+
+    #[test]
+    fn flags_extensions_that_can_take_or_lock_tokens() {
+        let delegate = Pubkey::new_unique();
+        let hook_program = Pubkey::new_unique();
+        let close_authority = Pubkey::new_unique();
+        let mint = mint(
+            &[
+                ExtensionType::PermanentDelegate,
+                ExtensionType::TransferHook,
+                ExtensionType::MintCloseAuthority,
+                ExtensionType::DefaultAccountState,
+                ExtensionType::NonTransferable,
+            ],
+            0,
+            |state| {
+                state
+                    .init_extension::<PermanentDelegate>(true)
+                    .unwrap()
+                    .delegate = key(delegate);
+                state
+                    .init_extension::<TransferHook>(true)
+                    .unwrap()
+                    .program_id = key(hook_program);
+                state
+                    .init_extension::<MintCloseAuthority>(true)
+                    .unwrap()
+                    .close_authority = key(close_authority);
+                state
+                    .init_extension::<DefaultAccountState>(true)
+                    .unwrap()
+                    .state = AccountState::Frozen as u8;
+                state.init_extension::<NonTransferable>(true).unwrap();
+            },
+        );
+        let extensions = MintExtensions::from_mint(&mint).unwrap();
+
+        assert_eq!(extensions.permanent_delegate, Some(delegate.to_string()));
+        assert_eq!(
+            extensions.transfer_hook_program,
+            Some(hook_program.to_string())
+        );
+        assert_eq!(
+            extensions.mint_close_authority,
+            Some(close_authority.to_string())
+        );
+        assert_eq!(
+            extensions.risk_flags(),
+            vec![
+                RiskFlag::PermanentDelegate,
+                RiskFlag::TransferHook,
+                RiskFlag::NonTransferable,
+                RiskFlag::DefaultFrozen,
+                RiskFlag::MintCloseAuthority,
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_metadata_stored_in_the_mint() {
+        let mint_address = Pubkey::new_unique();
+        let metadata = TokenMetadata {
+            update_authority: key(Pubkey::new_unique()),
+            mint: mint_address,
+            name: String::from("Pepe"),
+            symbol: String::from("PEPE"),
+            uri: String::from("https://arweave.net/pepe.json"),
+            additional_metadata: Vec::new(),
+        };
+        let metadata_len = 4 + metadata.tlv_size_of().unwrap();
+        let mint = mint(&[ExtensionType::MetadataPointer], metadata_len, |state| {
+            state
+                .init_extension::<MetadataPointer>(true)
+                .unwrap()
+                .metadata_address = key(mint_address);
+            state.init_variable_len_extension(&metadata, false).unwrap();
+        });
+        let extensions = MintExtensions::from_mint(&mint).unwrap();
+
+        assert_eq!(extensions.metadata_address, Some(mint_address.to_string()));
+        let stored = extensions.metadata.as_ref().unwrap();
+        assert_eq!(
+            (
+                stored.name.as_str(),
+                stored.symbol.as_str(),
+                stored.uri.as_str()
+            ),
+            ("Pepe", "PEPE", "https://arweave.net/pepe.json")
+        );
+        assert!(extensions.risk_flags().is_empty());
+    }
+}